@@ -0,0 +1,130 @@
+//! Registry of the bot's slash commands, which interactions are dispatched through.
+
+use anyhow::Context as _;
+use serenity::{
+    async_trait,
+    builder::{CreateApplicationCommand, CreateApplicationCommands},
+    model::{
+        application::{
+            command::Command,
+            interaction::{
+                application_command::ApplicationCommandInteraction,
+                message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
+                MessageInteraction,
+            },
+        },
+        id::GuildId,
+    },
+    prelude::*,
+};
+use std::{collections::HashMap, time::Duration};
+
+// how many times creating the commands is attempted before giving up
+const CREATE_ATTEMPTS: u32 = 5;
+// the wait before the first retry, doubled after each one
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+
+#[async_trait]
+pub trait SlashCommand: Send + Sync {
+    /// The name the command is registered and dispatched by
+    fn name(&self) -> &'static str;
+
+    /// Fills in the command's definition
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand;
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()>;
+
+    /// Handles interactions with components on the messages the command responded with
+    async fn component(
+        &self,
+        _ctx: &Context,
+        _interaction: &MessageComponentInteraction,
+        _message_interaction: &MessageInteraction,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Handles submissions of modals opened from components on the command's messages
+    async fn modal(
+        &self,
+        _ctx: &Context,
+        _interaction: &ModalSubmitInteraction,
+        _message_interaction: &MessageInteraction,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct Commands {
+    commands: HashMap<&'static str, Box<dyn SlashCommand>>,
+}
+
+impl Commands {
+    pub fn register(&mut self, command: impl SlashCommand + 'static) {
+        self.commands.insert(command.name(), Box::new(command));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn SlashCommand> {
+        self.commands.get(name).map(Box::as_ref)
+    }
+
+    /// Fills in the definitions of all registered commands, sorted by name
+    pub fn definitions<'a>(
+        &self,
+        definitions: &'a mut CreateApplicationCommands,
+    ) -> &'a mut CreateApplicationCommands {
+        let mut commands = self.commands.values().collect::<Vec<_>>();
+        commands.sort_by_key(|command| command.name());
+        for command in commands {
+            definitions.create_application_command(|definition| command.create(definition));
+        }
+        definitions
+    }
+
+    /// Registers the commands in the guild, or globally if no guild is given
+    pub async fn create(&self, guild_id: Option<GuildId>, ctx: &Context) -> anyhow::Result<()> {
+        match guild_id {
+            Some(guild_id) => guild_id
+                .set_application_commands(&ctx, |definitions| self.definitions(definitions))
+                .await
+                .with_context(|| format!("failed to create commands in {guild_id}"))?,
+            None => Command::set_global_application_commands(&ctx, |definitions| {
+                self.definitions(definitions)
+            })
+            .await
+            .context("failed to create global commands")?,
+        };
+        Ok(())
+    }
+
+    /// Registers the commands like `create`, retrying with exponential backoff so that a temporary
+    /// failure doesn't leave the guild without them
+    pub async fn create_with_retry(
+        &self,
+        guild_id: Option<GuildId>,
+        ctx: &Context,
+    ) -> anyhow::Result<()> {
+        let mut backoff = INITIAL_BACKOFF;
+        for _ in 1..CREATE_ATTEMPTS {
+            match self.create(guild_id, ctx).await {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    tracing::warn!("{err:#}, retrying in {}s", backoff.as_secs());
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+            }
+        }
+        self.create(guild_id, ctx)
+            .await
+            .with_context(|| format!("gave up after {CREATE_ATTEMPTS} attempts"))
+    }
+}
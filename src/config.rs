@@ -0,0 +1,158 @@
+//! The bot's configuration, read from the environment on startup.
+
+use crate::poll;
+use anyhow::Context as _;
+use serenity::model::id::{ChannelId, UserId};
+use std::{env, path::PathBuf, time::Duration};
+
+pub struct Config {
+    pub discord_token: String,
+    pub application_id: u64,
+    // registering commands in each guild is useful for development as they update instantly
+    pub guild_commands: bool,
+    pub polls_file: PathBuf,
+    // the file the guilds' poll settings are saved to
+    pub settings_file: PathBuf,
+    // the SQLite database every poll and vote is recorded in, if any
+    pub history_file: Option<PathBuf>,
+    // how often expired polls are checked for
+    pub cleaner_interval: Duration,
+    // logs the polls the cleaner would remove instead of removing them
+    pub cleaner_dry_run: bool,
+    // how long before a poll closes a reminder is posted, if at all
+    pub reminder: Option<Duration>,
+    // the owner can use the commands for managing the bot
+    pub owner_id: Option<UserId>,
+    // the channel searched for polls that weren't saved on startup, if any
+    pub recovery_channel: Option<ChannelId>,
+    // the port the healthcheck endpoint is served on, if at all
+    pub health_port: Option<u16>,
+    pub poll: poll::Config,
+}
+
+impl Config {
+    /// Reads and validates the configuration from the environment variables
+    pub fn from_env() -> anyhow::Result<Self> {
+        let discord_token = env::var("DISCORD_TOKEN").context("missing DISCORD_TOKEN")?;
+        let application_id = env::var("APPLICATION_ID").context("missing APPLICATION_ID")?;
+        let application_id = parse_id("APPLICATION_ID", &application_id)?;
+        let guild_commands = match env::var("GUILD_COMMANDS") {
+            Ok(guild_commands) => guild_commands.parse().context("invalid GUILD_COMMANDS")?,
+            Err(_) => false,
+        };
+        let polls_file = env::var("POLLS_FILE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from("polls.json"));
+        let settings_file = env::var("SETTINGS_FILE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from("settings.json"));
+        let history_file = env::var("HISTORY_FILE").ok().map(PathBuf::from);
+        let cleaner_interval = duration_from_env("CLEANER_INTERVAL_SECS", 60)?;
+        let cleaner_dry_run = match env::var("CLEANER_DRY_RUN") {
+            Ok(dry_run) => dry_run.parse().context("invalid CLEANER_DRY_RUN")?,
+            Err(_) => false,
+        };
+        let default_duration = duration_from_env("POLL_DURATION_SECS", 60 * 5)?;
+        if default_duration > poll::MAX_DURATION {
+            anyhow::bail!(
+                "invalid POLL_DURATION_SECS: must be at most {}",
+                poll::MAX_DURATION.as_secs()
+            );
+        }
+        let max_polls_per_user = match env::var("MAX_POLLS_PER_USER") {
+            Ok(max) => max.parse().context("invalid MAX_POLLS_PER_USER")?,
+            Err(_) => 5,
+        };
+        let reminder = match env::var("REMINDER_SECS") {
+            Ok(_) => Some(duration_from_env("REMINDER_SECS", 0)?),
+            Err(_) => None,
+        };
+        // zero disables the cooldown
+        let cooldown = match env::var("POLL_COOLDOWN_SECS") {
+            Ok(secs) => Duration::from_secs(secs.parse().context("invalid POLL_COOLDOWN_SECS")?),
+            Err(_) => Duration::ZERO,
+        };
+        let max_options = match env::var("MAX_POLL_OPTIONS") {
+            Ok(max) => max.parse().context("invalid MAX_POLL_OPTIONS")?,
+            Err(_) => poll::MAX_OPTIONS,
+        };
+        if max_options == 0 || max_options > poll::MAX_OPTIONS {
+            anyhow::bail!(
+                "invalid MAX_POLL_OPTIONS: must be between 1 and {}",
+                poll::MAX_OPTIONS
+            );
+        }
+        let min_account_age = duration_from_env("MIN_ACCOUNT_AGE_SECS", 60 * 60 * 24 * 7)?;
+        // zero updates the poll after every vote
+        let vote_batch = match env::var("VOTE_BATCH_MILLIS") {
+            Ok(millis) => millis.parse().context("invalid VOTE_BATCH_MILLIS")?,
+            Err(_) => 0,
+        };
+        let vote_batch = (vote_batch > 0).then(|| Duration::from_millis(vote_batch));
+        let max_active_polls = match env::var("MAX_ACTIVE_POLLS") {
+            Ok(max) => Some(max.parse().context("invalid MAX_ACTIVE_POLLS")?),
+            Err(_) => None,
+        };
+        if max_active_polls == Some(0) {
+            anyhow::bail!("invalid MAX_ACTIVE_POLLS: must be greater than zero");
+        }
+        let owner_id = match env::var("OWNER_ID") {
+            Ok(owner_id) => Some(UserId(parse_id("OWNER_ID", &owner_id)?)),
+            Err(_) => None,
+        };
+        let recovery_channel = match env::var("RECOVERY_CHANNEL_ID") {
+            Ok(channel_id) => Some(ChannelId(parse_id("RECOVERY_CHANNEL_ID", &channel_id)?)),
+            Err(_) => None,
+        };
+        let health_port = match env::var("HEALTH_PORT") {
+            Ok(port) => Some(port.parse().context("invalid HEALTH_PORT")?),
+            Err(_) => None,
+        };
+
+        Ok(Self {
+            discord_token,
+            application_id,
+            guild_commands,
+            polls_file,
+            settings_file,
+            history_file,
+            cleaner_interval,
+            cleaner_dry_run,
+            reminder,
+            owner_id,
+            recovery_channel,
+            health_port,
+            poll: poll::Config {
+                default_duration,
+                max_polls_per_user,
+                cooldown,
+                max_options,
+                min_account_age,
+                vote_batch,
+                max_active_polls,
+            },
+        })
+    }
+}
+
+/// Reads a non-zero number of seconds from the environment variable, if set
+fn duration_from_env(name: &str, default_secs: u64) -> anyhow::Result<Duration> {
+    let secs = match env::var(name) {
+        Ok(secs) => secs.parse().with_context(|| format!("invalid {name}"))?,
+        Err(_) => default_secs,
+    };
+    if secs == 0 {
+        anyhow::bail!("invalid {name}: must be greater than zero");
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// Parses the Discord ID read from the environment variable. IDs are never zero, so a zero is
+/// rejected here instead of causing confusing errors from Discord later
+fn parse_id(name: &str, id: &str) -> anyhow::Result<u64> {
+    let id = id.parse().with_context(|| format!("invalid {name}"))?;
+    if id == 0 {
+        anyhow::bail!("invalid {name}: must not be zero");
+    }
+    Ok(id)
+}
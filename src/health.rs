@@ -0,0 +1,65 @@
+//! A minimal HTTP endpoint reporting whether the bot is connected to Discord, for process managers
+//! and container orchestrators to restart the bot if it isn't.
+
+use anyhow::Context as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+// set once the bot is connected to the gateway, and unset while it's reconnecting
+static READY: AtomicBool = AtomicBool::new(false);
+
+pub fn set_ready(ready: bool) {
+    READY.store(ready, Ordering::Relaxed);
+}
+
+/// Responds to every request on the port with 200 while the bot is ready and 503 otherwise
+pub async fn serve(port: u16) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .await
+        .with_context(|| format!("failed to bind healthcheck port {port}"))?;
+    tracing::info!("serving healthcheck on port {port}");
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                tracing::warn!("Failed to accept healthcheck connection: {err}");
+                continue;
+            }
+        };
+        tokio::spawn(async move {
+            if let Err(err) = respond(stream).await {
+                tracing::warn!("Failed to respond to healthcheck: {err:#}");
+            }
+        });
+    }
+}
+
+async fn respond(mut stream: TcpStream) -> anyhow::Result<()> {
+    // the request doesn't matter, but it's read so that the client doesn't see a reset connection
+    let mut request = [0; 1024];
+    let _ = stream
+        .read(&mut request)
+        .await
+        .context("failed to read request")?;
+    let (status, body) = if READY.load(Ordering::Relaxed) {
+        ("200 OK", "ready")
+    } else {
+        ("503 Service Unavailable", "not ready")
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream
+        .write_all(response.as_bytes())
+        .await
+        .context("failed to write response")?;
+    stream
+        .shutdown()
+        .await
+        .context("failed to close connection")?;
+    Ok(())
+}
@@ -1,65 +1,216 @@
+mod command;
+mod config;
+mod health;
 mod poll;
 
 use anyhow::Context as _;
-use serenity::{async_trait, model::application::interaction::Interaction, prelude::*};
-use std::{env, time::Duration};
+use command::Commands;
+use config::Config;
+use serenity::{
+    async_trait,
+    client::bridge::gateway::event::ShardStageUpdateEvent,
+    gateway::ConnectionStage,
+    model::{application::interaction::Interaction, guild::Guild},
+    prelude::*,
+};
+use std::{sync::Arc, time::Duration};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenv::dotenv().ok();
     tracing_subscriber::fmt::init();
 
-    let discord_token = env::var("DISCORD_TOKEN").context("missing DISCORD_TOKEN")?;
-    let application_id: u64 = env::var("APPLICATION_ID")
-        .context("missing APPLICATION_ID")?
-        .parse()
-        .context("invalid APPLICATION_ID")?;
+    let config = Arc::new(Config::from_env()?);
 
-    let intents = GatewayIntents::GUILD_MESSAGES;
-    let mut client = Client::builder(discord_token, intents)
-        .event_handler(Handler)
-        .application_id(application_id)
+    poll::load(&config.polls_file)
+        .await
+        .context("failed to load saved polls")?;
+    poll::load_settings(&config.settings_file)
+        .await
+        .context("failed to load saved settings")?;
+
+    if let Some(history_file) = &config.history_file {
+        poll::open_history(history_file).context("failed to open HISTORY_FILE")?;
+    }
+
+    let mut commands = Commands::default();
+    poll::register(&mut commands, config.clone());
+
+    // guilds are needed for the guild create events used to register guild commands
+    let intents = GatewayIntents::GUILDS | GatewayIntents::GUILD_MESSAGES;
+    let mut client = Client::builder(&config.discord_token, intents)
+        .event_handler(Handler {
+            config: config.clone(),
+            commands,
+        })
+        .application_id(config.application_id)
         .await?;
 
+    // a wrong application ID would otherwise only show up as a confusing error when creating commands
+    let application_info = client
+        .cache_and_http
+        .http
+        .get_current_application_info()
+        .await
+        .context("failed to get application info, is DISCORD_TOKEN valid?")?;
+    if application_info.id.0 != config.application_id {
+        anyhow::bail!(
+            "invalid APPLICATION_ID: the token belongs to application {}",
+            application_info.id
+        );
+    }
+
+    if let Some(port) = config.health_port {
+        tokio::spawn(async move {
+            if let Err(err) = health::serve(port).await {
+                tracing::error!("Failed to serve healthcheck: {err:#}");
+            }
+        });
+    }
+
     tracing::info!("starting client");
-    let _handle = tokio::spawn(poll::cleaner(
+    let cleaner = tokio::spawn(poll::cleaner(
+        client.cache_and_http.http.clone(),
+        config.clone(),
+    ));
+    let saver = tokio::spawn(poll::saver(
+        config.polls_file.clone(),
         Duration::from_secs(60),
-        Duration::from_secs(60 * 5),
     ));
+    let flusher = config
+        .poll
+        .vote_batch
+        .map(|batch| tokio::spawn(poll::flusher(client.cache_and_http.http.clone(), batch)));
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        if let Err(err) = shutdown_signal().await {
+            tracing::error!("Failed to listen for shutdown signal: {err:#}");
+            return;
+        }
+        tracing::info!("shutting down");
+        // stops the client, making client.start return
+        shard_manager.lock().await.shutdown_all().await;
+    });
     client.start().await.context("failed to start client")?;
 
+    // stop the background tasks so that the polls don't change while they're being saved
+    cleaner.abort();
+    saver.abort();
+    if let Some(flusher) = flusher {
+        flusher.abort();
+    }
+    poll::save(&config.polls_file)
+        .await
+        .context("failed to save polls")?;
+    tracing::info!("saved polls to {}", config.polls_file.display());
+
     Ok(())
 }
 
-struct Handler;
+/// Waits for Ctrl+C, or SIGTERM on Unix
+async fn shutdown_signal() -> anyhow::Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            res = tokio::signal::ctrl_c() => res?,
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
+
+struct Handler {
+    config: Arc<Config>,
+    commands: Commands,
+}
 
 #[async_trait]
 impl EventHandler for Handler {
     async fn ready(&self, ctx: Context, data_about_bot: serenity::model::prelude::Ready) {
-        for guild in data_about_bot.guilds {
-            if guild.unavailable {
-                tracing::trace!("Guild unavailable: {}", guild.id);
-            } else if let Err(err) = poll::create(guild.id, &ctx).await {
-                tracing::error!("Failed to create poll command in {}: {err}", guild.id);
+        health::set_ready(true);
+        if let Some(channel_id) = self.config.recovery_channel {
+            // polls that are already stored are skipped, so reconnecting doesn't duplicate them
+            if let Err(err) = poll::recover(&ctx.http, channel_id, data_about_bot.user.id).await {
+                tracing::error!("Failed to recover polls: {err:#}");
             }
         }
+        if self.config.guild_commands {
+            return;
+        }
+        if let Err(err) = self.commands.create_with_retry(None, &ctx).await {
+            tracing::error!("Failed to create commands: {err:#}");
+        }
+    }
+
+    async fn shard_stage_update(&self, _ctx: Context, event: ShardStageUpdateEvent) {
+        // the bot isn't ready while it's reconnecting, and is ready again once it has resumed
+        health::set_ready(event.new == ConnectionStage::Connected);
+    }
+
+    async fn guild_create(&self, ctx: Context, guild: Guild) {
+        // sent for every guild the bot is in on startup, and for guilds it joins later
+        if !self.config.guild_commands {
+            return;
+        }
+        // a failure only affects this guild, the bot keeps running for the others
+        if let Err(err) = self.commands.create_with_retry(Some(guild.id), &ctx).await {
+            tracing::error!("Failed to create commands: {err:#}");
+        }
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
         let res = match interaction {
-            Interaction::ApplicationCommand(aci) => match aci.data.name.as_str() {
-                poll::COMMAND => poll::start(&ctx, aci).await,
-                _ => return,
-            },
+            Interaction::ApplicationCommand(aci) => {
+                let Some(command) = self.commands.get(&aci.data.name) else {
+                    return;
+                };
+                let res = command.run(&ctx, &aci).await;
+                match user_error(&res) {
+                    Some(err) => {
+                        poll::respond_ephemeral(&ctx, &aci, &err.localize(&aci.locale)).await
+                    }
+                    None => res,
+                }
+            }
             Interaction::MessageComponent(mci) => {
-                let (mi, msg) = if let Some(mi) = mci.message.interaction.as_ref() {
-                    (mi, &mi.name)
-                } else {
+                let Some(mi) = poll::message_interaction(&mci.message, &mci.data.custom_id).await
+                else {
+                    return;
+                };
+                let Some(command) = self.commands.get(&mi.name) else {
                     return;
                 };
-                match msg.as_str() {
-                    poll::COMMAND => poll::vote(&ctx, &mci, mi).await,
-                    _ => return,
+                let res = command.component(&ctx, &mci, &mi).await;
+                match user_error(&res) {
+                    Some(err) => {
+                        poll::respond_component_ephemeral(&ctx, &mci, &err.localize(&mci.locale))
+                            .await
+                    }
+                    None => res,
+                }
+            }
+            Interaction::ModalSubmit(msi) => {
+                // modals opened from components are submitted with the component's message
+                let Some(message) = msi.message.as_ref() else {
+                    return;
+                };
+                let Some(mi) = poll::message_interaction(message, &msi.data.custom_id).await else {
+                    return;
+                };
+                let Some(command) = self.commands.get(&mi.name) else {
+                    return;
+                };
+                let res = command.modal(&ctx, &msi, &mi).await;
+                match user_error(&res) {
+                    Some(err) => {
+                        poll::respond_modal_ephemeral(&ctx, &msi, &err.localize(&msi.locale)).await
+                    }
+                    None => res,
                 }
             }
             _ => return,
@@ -68,6 +219,11 @@ impl EventHandler for Handler {
     }
 }
 
+/// Returns the error if it should be shown to the user
+fn user_error<T>(res: &anyhow::Result<T>) -> Option<&poll::PollError> {
+    res.as_ref().err()?.downcast_ref()
+}
+
 fn print_errors<T>(res: &anyhow::Result<T>) {
     if let Err(err) = res {
         err.chain().for_each(|e| tracing::error!("{}", e));
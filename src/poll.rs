@@ -1,69 +1,831 @@
+mod commands;
+mod export;
+mod history;
+mod i18n;
+mod ranked;
+mod recover;
+mod settings;
+mod store;
+
+pub use self::{
+    commands::register, history::open as open_history, recover::recover,
+    settings::load as load_settings,
+};
+
+use self::{ranked::Runoff, store::Polls};
 use anyhow::Context as _;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use serenity::{
-    builder::{CreateActionRow, CreateButton},
+    builder::{
+        CreateActionRow, CreateApplicationCommands, CreateButton, CreateEmbed,
+        CreateInteractionResponseFollowup, CreateSelectMenu, CreateSelectMenuOption,
+    },
+    http::{Http, HttpError},
+    json::{self, Value},
     model::{
         application::{
-            command::{Command, CommandOptionType},
-            component::{ActionRowComponent, ButtonStyle},
+            command::Command,
+            component::{ActionRowComponent, ButtonStyle, ComponentType, InputTextStyle},
             interaction::{
                 application_command::ApplicationCommandInteraction, InteractionResponseType,
-                MessageInteraction,
+                InteractionType, MessageInteraction,
             },
         },
-        id::{GuildId, InteractionId, UserId},
+        channel::{Message, ReactionType},
+        guild::Member,
+        id::{ChannelId, GuildId, InteractionId, MessageId, RoleId, UserId},
         prelude::interaction::message_component::MessageComponentInteraction,
+        prelude::interaction::modal::ModalSubmitInteraction,
+        user::User,
+        Timestamp,
     },
     prelude::*,
 };
-use std::{collections::HashMap, time::Duration};
-use tokio::time::Instant;
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+const COMMAND: &str = "poll";
+const CLOSE_COMMAND: &str = "closepoll";
+const MY_VOTE_COMMAND: &str = "mypoll";
+const VOTERS_COMMAND: &str = "voters";
+const RESULTS_COMMAND: &str = "results";
+const HELP_COMMAND: &str = "pollhelp";
+const EXPORT_COMMAND: &str = "pollexport";
+const STATS_COMMAND: &str = "pollstats";
+const EDIT_COMMAND: &str = "polledit";
+const REFRESH_COMMAND: &str = "pollrefresh";
+const PURGE_COMMAND: &str = "pollpurge";
+const EXTEND_COMMAND: &str = "pollextend";
+const LIST_COMMAND: &str = "polls";
+const CLONE_COMMAND: &str = "pollclone";
+const CONFIG_COMMAND: &str = "pollconfig";
+const RELOAD_COMMAND: &str = "pollreload";
+const TRANSFER_COMMAND: &str = "polltransfer";
+
+// Discord's limits for message components
+const BUTTONS_PER_ROW: usize = 5;
+const MAX_ROWS: usize = 5;
+pub const MAX_OPTIONS: usize = BUTTONS_PER_ROW * MAX_ROWS;
+// voting buttons' IDs are prefixed with poll_opt: and the other components' with poll_ctl:
+// so that options can't be mistaken for them
+const OPTION_PREFIX: &str = "poll_opt:";
+const SELECT_MENU_ID: &str = "poll_ctl:vote";
+// the button for showing who voted for what
+const VOTERS_ID: &str = "poll_ctl:voters";
+// the buttons on the preview of an important poll
+const POST_DRAFT_ID: &str = "poll_ctl:post";
+const CANCEL_DRAFT_ID: &str = "poll_ctl:cancel";
+// the button for cancelling a scheduled poll before it starts
+const UNSCHEDULE_ID: &str = "poll_ctl:unschedule";
+// the button for opening a panel that only the voter sees with their vote highlighted, and the
+// prefix of the panel's buttons, which are followed by the poll's ID and the option's position
+const MY_VOTE_ID: &str = "poll_ctl:mine";
+const SWITCH_PREFIX: &str = "poll_ctl:switch:";
+// the button for adding an option, and the modal it opens
+const ADD_OPTION_ID: &str = "poll_ctl:add";
+const ADD_OPTION_INPUT_ID: &str = "option";
+// the vote ID of abstaining, which can't be used as an option in polls with an abstain button
+const ABSTAIN_ID: &str = "Abstain";
+// interaction tokens, which are needed to post the poll, are only valid for 15 minutes
+const DRAFT_TIMEOUT: Duration = Duration::from_secs(60 * 15);
+// how soon after a vote another click by the same user in the same poll is ignored
+const VOTE_DEBOUNCE: Duration = Duration::from_millis(500);
+// button labels can be at most 80 characters, leaving room for the ": {votes}" suffix
+const MAX_OPTION_LENGTH: usize = 72;
+// the weighted votes are summed as u32, which this keeps far from overflowing
+const MAX_WEIGHT: u32 = 100;
+// keeps the title from taking up too much of the message content
+const MAX_TITLE_LENGTH: u16 = 200;
+// thread names can be at most 100 characters
+const MAX_THREAD_NAME_LENGTH: usize = 100;
+
+// the members are listed in pages of at most 1000, and servers with more than 10 pages of
+// members are too large to count them for every poll
+const MEMBERS_PER_PAGE: u64 = 1000;
+const MAX_MEMBER_PAGES: usize = 10;
+
+// Discord's error codes for interactions that can't be responded to
+const UNKNOWN_INTERACTION: isize = 10062;
+const ALREADY_ACKNOWLEDGED: isize = 40060;
+
+pub const MAX_DURATION: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+/// Defaults and limits for polls set by the bot's operator
+#[derive(Clone, Copy)]
+pub struct Config {
+    // the duration of polls that don't specify one
+    pub default_duration: Duration,
+    // how many polls each user can have running at once
+    pub max_polls_per_user: usize,
+    // how long to wait between starting polls in the same channel
+    pub cooldown: Duration,
+    // how many options each poll can have, at most MAX_OPTIONS
+    pub max_options: usize,
+    // how old accounts need to be to take part in polls that only allow verified voters
+    pub min_account_age: Duration,
+    // how often the messages of polls that were voted in are updated, if not after every vote
+    pub vote_batch: Option<Duration>,
+    // how many polls can be kept at once before the oldest are closed, if limited
+    pub max_active_polls: Option<usize>,
+}
+
+/// Errors caused by the user, reported back to them instead of being logged
+#[derive(Debug)]
+pub enum PollError {
+    NoOptions,
+    InvisibleOption,
+    TooManyOptions(usize, usize),
+    OptionTooLong(String),
+    DuplicateOptions(usize),
+    InvalidDuration(String),
+    DurationTooLong,
+    PollEnded,
+    PollClosed,
+    MultiAndRanked,
+    PollNotFound,
+    NotCreator,
+    TooManyPolls(usize),
+    Anonymous,
+    ResultsHidden,
+    ReservedOption(String),
+    MissingRole,
+    Unverified(u64),
+    NotOwner,
+    OptionNotFound(String),
+    OptionExists(String),
+    OptionFull(String),
+    Cooldown(u64),
+    DraftExpired,
+    InvalidEndTime(String),
+    EndInPast,
+    DurationAndEnd,
+    InvalidStartTime(String),
+    StartInPast,
+    StartTooLate,
+    InvalidWeights(String),
+    WeightTooHigh,
+    InvalidImage(String),
+}
+
+impl PollError {
+    /// The error message in the locale of the user who caused it
+    pub fn localize(&self, locale: &str) -> String {
+        let text = |key| i18n::text(locale, key).to_string();
+        let format = |key, args: &[(&str, &dyn std::fmt::Display)]| i18n::format(locale, key, args);
+        match self {
+            Self::NoOptions => text("error_no_options"),
+            Self::InvisibleOption => text("error_invisible_option"),
+            Self::TooManyOptions(count, max) => {
+                format("error_too_many_options", &[("count", count), ("max", max)])
+            }
+            Self::OptionTooLong(option) => format(
+                "error_option_too_long",
+                &[("option", option), ("max", &MAX_OPTION_LENGTH)],
+            ),
+            Self::DuplicateOptions(1) => text("error_duplicate_options_one"),
+            Self::DuplicateOptions(count) => format("error_duplicate_options", &[("count", count)]),
+            Self::InvalidDuration(duration) => {
+                format("error_invalid_duration", &[("duration", duration)])
+            }
+            Self::DurationTooLong => text("error_duration_too_long"),
+            Self::InvalidEndTime(ends) => format("error_invalid_end_time", &[("ends", ends)]),
+            Self::EndInPast => text("error_end_in_past"),
+            Self::DurationAndEnd => text("error_duration_and_end"),
+            Self::InvalidStartTime(starts) => {
+                format("error_invalid_start_time", &[("starts", starts)])
+            }
+            Self::StartInPast => text("error_start_in_past"),
+            Self::StartTooLate => text("error_start_too_late"),
+            Self::InvalidWeights(weights) => {
+                format("error_invalid_weights", &[("weights", weights)])
+            }
+            Self::WeightTooHigh => format("error_weight_too_high", &[("max", &MAX_WEIGHT)]),
+            Self::InvalidImage(image) => format("error_invalid_image", &[("image", image)]),
+            Self::PollEnded => text("error_poll_ended"),
+            Self::PollClosed => text("error_poll_closed"),
+            Self::MultiAndRanked => text("error_multi_and_ranked"),
+            Self::PollNotFound => text("error_poll_not_found"),
+            Self::NotCreator => text("error_not_creator"),
+            Self::TooManyPolls(max) => format("error_too_many_polls", &[("max", max)]),
+            Self::Anonymous => text("error_anonymous"),
+            Self::ResultsHidden => text("error_results_hidden"),
+            Self::ReservedOption(option) => format("error_reserved_option", &[("option", option)]),
+            Self::MissingRole => text("error_missing_role"),
+            Self::Unverified(days) => format("error_unverified", &[("days", days)]),
+            Self::NotOwner => text("error_not_owner"),
+            Self::OptionNotFound(option) => format("error_option_not_found", &[("option", option)]),
+            Self::OptionExists(option) => format("error_option_exists", &[("option", option)]),
+            Self::DraftExpired => text("error_draft_expired"),
+            Self::OptionFull(option) => format("error_option_full", &[("option", option)]),
+            Self::Cooldown(1) => text("error_cooldown_one"),
+            Self::Cooldown(secs) => format("error_cooldown", &[("secs", secs)]),
+        }
+    }
+}
+
+impl std::fmt::Display for PollError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.localize(i18n::DEFAULT_LOCALE))
+    }
+}
+
+impl std::error::Error for PollError {}
+
+static POLLS: Lazy<Polls> = Lazy::new(Polls::new);
+// important polls waiting for their creators to confirm them, by the poll command's ID
+static DRAFTS: Lazy<Mutex<HashMap<InteractionId, Draft>>> = Lazy::new(Default::default);
+// when each channel can next have a poll started in it
+static COOLDOWNS: Lazy<Mutex<HashMap<ChannelId, Instant>>> = Lazy::new(Default::default);
+// when each user last voted in each poll, for ignoring repeated clicks
+static LAST_VOTES: Lazy<Mutex<HashMap<(InteractionId, UserId), Instant>>> =
+    Lazy::new(Default::default);
+// the polls voted in since their messages were last updated, when vote updates are batched
+static OUTDATED: Lazy<Mutex<HashSet<InteractionId>>> = Lazy::new(Default::default);
+// usage counters since the bot was started, reported by the stats command
+static POLLS_CREATED: AtomicU64 = AtomicU64::new(0);
+static VOTES_CAST: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum PollKind {
+    // each user can vote for one option
+    Single,
+    // each user can vote for several options
+    Multi,
+    // each user ranks the options in order of preference
+    Ranked,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum TiePolicy {
+    // all of the tied options are reported as winners
+    Report,
+    // the tied option listed first in the poll wins
+    First,
+    // there's no winner until the tied options are voted on again
+    Runoff,
+}
 
-pub const COMMAND: &str = "poll";
+/// The order the options are shown in
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum SortOrder {
+    // in the order the creator entered them
+    Entered,
+    // alphabetically
+    Alpha,
+    // by their current votes, most first
+    Votes,
+}
+
+/// The colors of the options' buttons
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ButtonColors {
+    // blurple
+    #[default]
+    Primary,
+    // grey
+    Secondary,
+    // green
+    Success,
+    // red
+    Danger,
+    // each option in a different color from the previous one, in the order they were entered
+    Cycle,
+}
+
+impl ButtonColors {
+    // the order the colors are cycled through in
+    const PALETTE: [ButtonStyle; 4] = [
+        ButtonStyle::Primary,
+        ButtonStyle::Success,
+        ButtonStyle::Danger,
+        ButtonStyle::Secondary,
+    ];
 
-static POLLS: Lazy<RwLock<HashMap<InteractionId, PollData>>> =
-    Lazy::new(|| RwLock::new(HashMap::new()));
+    /// The style of the button of the option at the position in the poll's options
+    fn style(self, position: usize) -> ButtonStyle {
+        match self {
+            Self::Primary => ButtonStyle::Primary,
+            Self::Secondary => ButtonStyle::Secondary,
+            Self::Success => ButtonStyle::Success,
+            Self::Danger => ButtonStyle::Danger,
+            Self::Cycle => Self::PALETTE[position % Self::PALETTE.len()],
+        }
+    }
+}
 
+#[derive(Clone, Serialize, Deserialize)]
 struct PollData {
-    start_time: Instant,
+    // wall-clock times so that they can be persisted across restarts
+    start_time: SystemTime,
+    end_time: SystemTime,
+    // whether the poll is waiting for its start time to be posted by the cleaner
+    scheduled: bool,
+    // whether the reminder before the poll closes has been sent
+    reminded: bool,
+    // whether the poll message is pinned until the poll closes
+    pinned: bool,
+    creator: UserId,
+    guild_id: Option<GuildId>,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    closed: bool,
+    title: Option<String>,
+    // a link to an image shown in the poll's embed
+    image: Option<String>,
     options: Vec<String>,
-    votes: HashMap<UserId, String>,
+    kind: PollKind,
+    // anonymous polls never reveal who voted for what
+    anonymous: bool,
+    // the votes aren't shown until the poll closes so that they can't sway voters
+    hide_results: bool,
+    // the results are posted in a thread off the poll instead of the channel once it closes
+    thread_results: bool,
+    // the number of voters needed for the poll to be valid
+    quorum: Option<u32>,
+    // the number of voters after which the poll closes early
+    target_votes: Option<u32>,
+    // whether users can abstain, which counts as participation without voting for an option
+    abstain: bool,
+    // only members with the role can vote
+    role: Option<RoleId>,
+    // how many members had the role when the poll was posted, if they could be counted
+    eligible: Option<u32>,
+    // how the winner is decided if several options have the most votes
+    ties: TiePolicy,
+    sort: SortOrder,
+    // the colors of the options' buttons, from the guild's settings when the poll was started
+    colors: ButtonColors,
+    // whether voters can add their own options while the poll is open
+    open_additions: bool,
+    // whether options that only differ by case are different options
+    case_sensitive: bool,
+    // only accounts that are old enough and have passed membership screening can take part
+    verified_only: bool,
+    // the role mentioned when the poll is posted
+    ping: Option<RoleId>,
+    // the creator's locale, which the poll's messages are written in
+    locale: String,
+    // the options each user voted for, in the order they clicked them, or just ABSTAIN_ID
+    votes: HashMap<UserId, Vec<String>>,
+    // the maximum number of votes for options with a capacity
+    caps: HashMap<String, u32>,
+    // how much the votes of members with each role count, the highest of a member's roles applies
+    weights: HashMap<RoleId, u32>,
+    // the weight of each voter's votes when they voted, if it isn't 1
+    voter_weights: HashMap<UserId, u32>,
+    // votes for each option of a poll recovered from its message, which aren't tied to users
+    recovered_tallies: HashMap<String, u32>,
+    // the number of votes for each option, kept up to date with the votes by set_votes
+    #[serde(skip)]
+    tallies: HashMap<String, u32>,
+    // the sum of the weights of the votes for each option, kept up to date like the tallies
+    #[serde(skip)]
+    weighted_tallies: HashMap<String, u32>,
+}
+
+/// An important poll that hasn't been posted yet
+struct Draft {
+    poll_data: PollData,
+    // the poll command's token, used to post the poll as a followup to it
+    token: String,
+    created: Instant,
+    config: Config,
+    // the poll's duration if it was given as one instead of an end time
+    duration: Option<Duration>,
 }
 
 impl PollData {
+    /// The weighted number of votes for the option, or first preferences for ranked polls
     fn votes_for(&self, vote_id: &str) -> u32 {
-        let mut votes = 0;
-        for vote in self.votes.values() {
-            if vote == vote_id {
-                votes += 1;
+        self.weighted_tallies
+            .get(option_id(vote_id))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The number of users who voted for the option, regardless of their weights
+    fn voters_for(&self, vote_id: &str) -> u32 {
+        self.tallies
+            .get(option_id(vote_id))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn is_weighted(&self) -> bool {
+        !self.weights.is_empty()
+    }
+
+    /// How much a user's votes count, based on their roles when they voted
+    fn weight_of(&self, user_id: UserId) -> u32 {
+        self.voter_weights.get(&user_id).copied().unwrap_or(1)
+    }
+
+    /// How much the votes of a member with the roles count
+    fn weight_for_roles(&self, roles: &[RoleId]) -> u32 {
+        roles
+            .iter()
+            .filter_map(|role| self.weights.get(role))
+            .copied()
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// The part of a user's votes that counts towards the tallies
+    fn counted<'a>(&self, votes: &'a [String]) -> &'a [String] {
+        match self.kind {
+            PollKind::Ranked => &votes[..votes.len().min(1)],
+            PollKind::Single | PollKind::Multi => votes,
+        }
+    }
+
+    /// Recalculates the tallies from the votes, such as after loading the poll
+    fn recount(&mut self) {
+        let mut tallies = self.recovered_tallies.clone();
+        let mut weighted_tallies = self.recovered_tallies.clone();
+        for (user_id, votes) in &self.votes {
+            for vote in self.counted(votes) {
+                *tallies.entry(vote.clone()).or_default() += 1;
+                *weighted_tallies.entry(vote.clone()).or_default() += self.weight_of(*user_id);
+            }
+        }
+        self.tallies = tallies;
+        self.weighted_tallies = weighted_tallies;
+    }
+
+    /// Replaces the user's votes with ones of the given weight, updating the tallies to match
+    fn set_votes(
+        &mut self,
+        user_id: UserId,
+        votes: Vec<String>,
+        weight: u32,
+    ) -> Result<(), PollError> {
+        // votes the user already had count towards the capacity
+        let old = self
+            .votes
+            .get(&user_id)
+            .map(|old| self.counted(old))
+            .unwrap_or_default();
+        for vote in self.counted(&votes) {
+            if !old.contains(vote) && self.is_full(vote) {
+                return Err(PollError::OptionFull(vote.clone()));
+            }
+        }
+
+        let old_weight = self.weight_of(user_id);
+        let old = if votes.is_empty() {
+            self.voter_weights.remove(&user_id);
+            self.votes.remove(&user_id)
+        } else {
+            if weight == 1 {
+                self.voter_weights.remove(&user_id);
+            } else {
+                self.voter_weights.insert(user_id, weight);
+            }
+            self.votes.insert(user_id, votes)
+        };
+        for vote in self.counted(old.as_deref().unwrap_or_default()) {
+            for (tallies, amount) in [
+                (&mut self.tallies, 1),
+                (&mut self.weighted_tallies, old_weight),
+            ] {
+                if let Some(tally) = tallies.get_mut(vote) {
+                    *tally -= amount;
+                    if *tally == 0 {
+                        tallies.remove(vote);
+                    }
+                }
+            }
+        }
+        let new = self.votes.get(&user_id).cloned().unwrap_or_default();
+        for vote in self.counted(&new) {
+            *self.tallies.entry(vote.clone()).or_default() += 1;
+            *self.weighted_tallies.entry(vote.clone()).or_default() += weight;
+        }
+        Ok(())
+    }
+
+    /// Whether the option has as many votes as it can take
+    fn is_full(&self, vote_id: &str) -> bool {
+        self.caps
+            .get(option_id(vote_id))
+            .map_or(false, |cap| self.voters_for(vote_id) >= *cap)
+    }
+
+    /// Whether the votes are hidden until the poll closes
+    fn results_hidden(&self) -> bool {
+        self.hide_results && !self.closed
+    }
+
+    /// The options in the order they're shown in
+    fn sorted_options(&self) -> Vec<&String> {
+        let mut options = self.options.iter().collect::<Vec<_>>();
+        // the order would give away which options are leading
+        if self.sort == SortOrder::Votes && !self.results_hidden() {
+            // the sort is stable, so tied options stay in the order they were entered
+            options.sort_by_key(|option| std::cmp::Reverse(self.votes_for(option)));
+        }
+        options
+    }
+
+    /// The number of votes for the option, or its voters followed by its capacity if it has one
+    fn format_votes(&self, vote_id: &str) -> String {
+        match self.caps.get(option_id(vote_id)) {
+            Some(cap) => format!("{}/{cap}", self.voters_for(vote_id)),
+            None => self.votes_for(vote_id).to_string(),
+        }
+    }
+
+    /// Runs an instant-runoff tabulation on the votes, using the options' IDs
+    fn runoff(&self) -> Runoff<'_> {
+        let options = self
+            .options
+            .iter()
+            .map(|option| option_id(option))
+            .collect::<Vec<_>>();
+        let ballots = self
+            .votes
+            .iter()
+            .map(|(user_id, votes)| (votes.as_slice(), self.weight_of(*user_id)))
+            .collect::<Vec<_>>();
+        ranked::instant_runoff(&options, &ballots)
+    }
+
+    /// The number of users who abstained
+    fn abstentions(&self) -> u32 {
+        if self.abstain {
+            self.voters_for(ABSTAIN_ID)
+        } else {
+            0
+        }
+    }
+
+    /// Whether the ID is used for something other than an option
+    fn is_reserved(&self, vote_id: &str) -> bool {
+        self.abstain && vote_id == ABSTAIN_ID
+    }
+
+    fn is_abstention(&self, vote: &[String]) -> bool {
+        self.abstain && vote.iter().any(|v| v == ABSTAIN_ID)
+    }
+
+    /// The number of votes still needed to reach the quorum
+    fn missing_votes(&self) -> u32 {
+        let voters = self.votes.len() as u32;
+        self.quorum.unwrap_or(0).saturating_sub(voters)
+    }
+
+    /// Returns the winners according to the tie policy, several if they're tied
+    fn winners(&self) -> Vec<&str> {
+        let mut winners = self.leaders();
+        if self.ties == TiePolicy::First {
+            winners.truncate(1);
+        }
+        winners
+    }
+
+    /// Returns the options with the most votes, if any votes were cast
+    fn leaders(&self) -> Vec<&str> {
+        if self.kind == PollKind::Ranked {
+            return self
+                .runoff()
+                .winners
+                .into_iter()
+                .filter_map(|id| self.option(id))
+                .collect();
+        }
+        let tallies = self
+            .options
+            .iter()
+            .map(|option| (option.as_str(), self.votes_for(option)))
+            .collect::<Vec<_>>();
+        let max = tallies.iter().map(|(_, votes)| *votes).max().unwrap_or(0);
+        if max == 0 {
+            return vec![];
+        }
+        tallies
+            .into_iter()
+            .filter(|(_, votes)| *votes == max)
+            .map(|(option, _)| option)
+            .collect()
+    }
+
+    /// The option's position in the options, which is used as the ID of its button and select
+    /// menu option instead of its text, which could be too long for one. The positions don't
+    /// change, since options are only ever renamed or added to the end
+    fn position(&self, option: &str) -> usize {
+        self.options
+            .iter()
+            .position(|o| o == option)
+            .unwrap_or_default()
+    }
+
+    /// Resolves the ID of an option's button or select menu option to the option's vote ID.
+    /// Components from before the IDs were positions have the option's text instead
+    fn resolve_component_id<'a>(&'a self, id: &'a str) -> &'a str {
+        match id.parse::<usize>().ok().and_then(|i| self.options.get(i)) {
+            Some(option) => option_id(option),
+            None => id,
+        }
+    }
+
+    /// Finds the option with the given ID
+    fn option(&self, vote_id: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .map(String::as_str)
+            .find(|option| option_id(option) == vote_id)
+    }
+
+    /// A new poll by the user with the same options and settings, but without any votes
+    fn rerun(&self, command: &ApplicationCommandInteraction) -> PollData {
+        let start_time = SystemTime::now();
+        let duration = self
+            .end_time
+            .duration_since(self.start_time)
+            .unwrap_or_default()
+            .min(MAX_DURATION);
+        PollData {
+            start_time,
+            end_time: start_time + duration,
+            scheduled: false,
+            reminded: false,
+            pinned: false,
+            creator: command.user.id,
+            guild_id: command.guild_id,
+            channel_id: command.channel_id,
+            message_id: MessageId::default(),
+            closed: false,
+            title: self.title.clone(),
+            image: self.image.clone(),
+            options: self.options.clone(),
+            kind: self.kind,
+            anonymous: self.anonymous,
+            hide_results: self.hide_results,
+            thread_results: self.thread_results,
+            quorum: self.quorum,
+            target_votes: self.target_votes,
+            abstain: self.abstain,
+            role: self.role,
+            // counted again when the poll is posted
+            eligible: None,
+            ties: self.ties,
+            sort: self.sort,
+            colors: self.colors,
+            open_additions: self.open_additions,
+            case_sensitive: self.case_sensitive,
+            verified_only: self.verified_only,
+            ping: self.ping,
+            locale: command.locale.clone(),
+            votes: HashMap::new(),
+            caps: self.caps.clone(),
+            weights: self.weights.clone(),
+            voter_weights: HashMap::new(),
+            recovered_tallies: HashMap::new(),
+            tallies: HashMap::new(),
+            weighted_tallies: HashMap::new(),
+        }
+    }
+
+    /// Finds the index of the option that the given one would be a duplicate of
+    fn similar_option(&self, option: &str) -> Option<usize> {
+        let normalized = normalize_option(option, self.case_sensitive);
+        self.options
+            .iter()
+            .position(|other| normalize_option(other, self.case_sensitive) == normalized)
+    }
+
+    /// Renames the option at the index, moving its votes to the new name
+    fn rename(&mut self, index: usize, new: String) {
+        let old_id = option_id(&self.options[index]).to_string();
+        let new_id = option_id(&new).to_string();
+        for votes in self.votes.values_mut() {
+            for vote in votes.iter_mut().filter(|vote| **vote == old_id) {
+                *vote = new_id.clone();
+            }
+        }
+        // the recovered votes are moved too so that recounting doesn't bring back the old option
+        for tallies in [
+            &mut self.tallies,
+            &mut self.weighted_tallies,
+            &mut self.recovered_tallies,
+        ] {
+            if let Some(tally) = tallies.remove(&old_id) {
+                tallies.insert(new_id.clone(), tally);
+            }
+        }
+        if let Some(cap) = self.caps.remove(&old_id) {
+            self.caps.insert(new_id, cap);
+        }
+        self.options[index] = new;
+    }
+
+    /// Replaces the user's votes with the options selected in the select menu
+    fn select(&mut self, user_id: UserId, values: &[String], weight: u32) -> Result<(), PollError> {
+        // abstaining overrides any options selected alongside it
+        if self.is_abstention(values) {
+            return self.set_votes(user_id, vec![ABSTAIN_ID.to_string()], weight);
+        }
+        let mut votes = Vec::<String>::new();
+        for value in values {
+            // each option can only be voted for once
+            if self.option(value).is_some() && !votes.contains(value) {
+                votes.push(value.clone());
+            }
+        }
+        if self.kind != PollKind::Multi {
+            votes.truncate(1);
+        }
+        self.set_votes(user_id, votes, weight)
+    }
+
+    fn vote(&mut self, user_id: UserId, vote_id: &str, weight: u32) -> Result<(), PollError> {
+        let vote_id = option_id(vote_id);
+        let abstaining = self.abstain && vote_id == ABSTAIN_ID;
+        // buttons from before an option was renamed can still be clicked
+        if !abstaining && self.option(vote_id).is_none() {
+            return Err(PollError::OptionNotFound(vote_id.to_string()));
+        }
+        let abstained = self
+            .votes
+            .get(&user_id)
+            .map_or(false, |votes| self.is_abstention(votes));
+        let mut votes = self.votes.get(&user_id).cloned().unwrap_or_default();
+        // abstaining replaces the user's votes, and voting replaces their abstention
+        if abstaining != abstained {
+            votes.clear();
+        }
+        let position = votes.iter().position(|v| v == vote_id);
+        match (self.kind, position) {
+            // clicking a selected option removes it from the user's selection or ranking
+            (PollKind::Multi | PollKind::Ranked, Some(position)) => {
+                votes.remove(position);
             }
+            (PollKind::Multi | PollKind::Ranked, None) => votes.push(vote_id.to_string()),
+            // clicking the current vote again retracts it
+            (PollKind::Single, Some(_)) => votes.clear(),
+            (PollKind::Single, None) => votes = vec![vote_id.to_string()],
         }
-        votes
+        self.set_votes(user_id, votes, weight)
     }
 }
 
-pub async fn create(guild_id: GuildId, ctx: &Context) -> anyhow::Result<Command> {
-    let res = guild_id
-        .create_application_command(&ctx, |command| {
-            command
-                .name(COMMAND)
-                .description("A simple poll command.")
-                .create_option(|option| {
-                    option
-                        .name("options")
-                        .kind(CommandOptionType::String)
-                        .description("Comma-separated list of options.")
-                        .required(true)
-                })
-        })
-        .await
-        .context("failed to create poll command")?;
-    Ok(res)
+/// Checks that the user can start a poll in the channel
+async fn check_can_start(
+    command: &ApplicationCommandInteraction,
+    config: &Config,
+) -> anyhow::Result<()> {
+    // limit how often polls can be started in each channel
+    if let Some(until) = COOLDOWNS.lock().await.get(&command.channel_id) {
+        let remaining = until.saturating_duration_since(Instant::now());
+        if !remaining.is_zero() {
+            // round up so that the user isn't told to wait 0 seconds
+            let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+            anyhow::bail!(PollError::Cooldown(secs));
+        }
+    }
+
+    // limit how many polls each user can have running at once
+    let mut active_polls = 0;
+    for shard in POLLS.shards() {
+        active_polls += shard
+            .read()
+            .await
+            .values()
+            .filter(|poll_data| poll_data.creator == command.user.id && !poll_data.closed)
+            .count();
+    }
+    if active_polls >= config.max_polls_per_user {
+        anyhow::bail!(PollError::TooManyPolls(config.max_polls_per_user));
+    }
+    Ok(())
 }
 
-pub async fn start(ctx: &Context, command: ApplicationCommandInteraction) -> anyhow::Result<()> {
+async fn start(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    config: &Config,
+) -> anyhow::Result<()> {
+    // the guild's settings replace the operator's defaults
+    let settings = settings::get(command.guild_id).await;
+    let config = &settings.apply(config);
+    let Config {
+        default_duration,
+        max_options,
+        ..
+    } = *config;
+    check_can_start(command, config).await?;
+
     // collect and validate poll options
-    let mut options = command
+    let options = command
         .data
         .options
         .iter()
@@ -73,131 +835,4130 @@ pub async fn start(ctx: &Context, command: ApplicationCommandInteraction) -> any
         .as_ref()
         .context("missing options value")?
         .as_str()
-        .context("invalid options value")?
-        .split(",")
+        .context("invalid options value")?;
+    let strict = get_option(command, "strict")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let verified_only = get_option(command, "verified_only")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let case_sensitive = get_option(command, "case_sensitive")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let sort = match get_option(command, "sort").and_then(|v| v.as_str()) {
+        Some("entered") => SortOrder::Entered,
+        Some("votes") => SortOrder::Votes,
+        _ => SortOrder::Alpha,
+    };
+    let abstain = get_option(command, "abstain")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    // the abstain button takes up one of the options' spots
+    let max_options = max_options.min(MAX_OPTIONS - usize::from(abstain));
+    let ParsedOptions {
+        options,
+        caps,
+        duplicates,
+    } = parse_options(options, max_options, strict, case_sensitive, sort)?;
+    let title = get_option(command, "title")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
         .filter(|s| !s.is_empty())
-        .collect::<Vec<_>>();
-    if options.is_empty() {
-        anyhow::bail!("no options");
+        .map(String::from);
+    let image = get_option(command, "image")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from);
+    // Discord only shows images from http and https links, and rejects the message otherwise
+    if let Some(image) = &image {
+        if !image.starts_with("https://") && !image.starts_with("http://") {
+            anyhow::bail!(PollError::InvalidImage(image.clone()));
+        }
     }
-    options.sort();
-    options.dedup();
+    // scheduled polls are posted by the cleaner once they start
+    let now = SystemTime::now();
+    let scheduled = get_option(command, "starts").and_then(|v| v.as_str());
+    let start_time = match scheduled {
+        // either how long until the poll starts, or when it starts
+        Some(starts) => {
+            let start_time = match parse_duration(starts) {
+                Ok(wait) => now + wait,
+                Err(PollError::DurationTooLong) => anyhow::bail!(PollError::StartTooLate),
+                Err(_) => parse_end_time(starts)
+                    .ok_or_else(|| PollError::InvalidStartTime(starts.to_string()))?,
+            };
+            match start_time.duration_since(now) {
+                Ok(wait) if wait > MAX_DURATION => anyhow::bail!(PollError::StartTooLate),
+                Ok(_) => start_time,
+                Err(_) => anyhow::bail!(PollError::StartInPast),
+            }
+        }
+        None => now,
+    };
+    let duration = get_option(command, "duration").and_then(|v| v.as_str());
+    let ends = get_option(command, "ends").and_then(|v| v.as_str());
+    // the duration is kept for drafts so that it can start once the poll is posted
+    let (end_time, duration) = match (duration, ends) {
+        (Some(_), Some(_)) => anyhow::bail!(PollError::DurationAndEnd),
+        (Some(duration), None) => {
+            let duration = parse_duration(duration)?;
+            (start_time + duration, Some(duration))
+        }
+        (None, Some(ends)) => {
+            let end_time =
+                parse_end_time(ends).ok_or_else(|| PollError::InvalidEndTime(ends.to_string()))?;
+            match end_time.duration_since(start_time) {
+                Ok(duration) if duration > MAX_DURATION => {
+                    anyhow::bail!(PollError::DurationTooLong)
+                }
+                Ok(_) => (end_time, None),
+                Err(_) => anyhow::bail!(PollError::EndInPast),
+            }
+        }
+        (None, None) => (start_time + default_duration, Some(default_duration)),
+    };
+    let multi = get_option(command, "multi")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let ranked = get_option(command, "ranked")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let kind = match (multi, ranked) {
+        (false, false) => PollKind::Single,
+        (true, false) => PollKind::Multi,
+        (false, true) => PollKind::Ranked,
+        (true, true) => anyhow::bail!(PollError::MultiAndRanked),
+    };
+    let anonymous = get_option(command, "anonymous")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(settings.anonymous);
+    let hide_results = get_option(command, "hide_results")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let thread_results = get_option(command, "thread_results")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let quorum = get_option(command, "quorum")
+        .and_then(|v| v.as_u64())
+        .map(|quorum| quorum.try_into().unwrap_or(u32::MAX));
+    let target_votes = get_option(command, "target_votes")
+        .and_then(|v| v.as_u64())
+        .map(|target| target.try_into().unwrap_or(u32::MAX));
+    // role options are sent as the role's ID in a string
+    let role = get_option(command, "role")
+        .and_then(|v| v.as_str())
+        .map(|role| role.parse().context("invalid role value"))
+        .transpose()?
+        .map(RoleId);
+    let ping = get_option(command, "ping")
+        .and_then(|v| v.as_str())
+        .map(|role| role.parse().context("invalid ping value"))
+        .transpose()?
+        .map(RoleId);
+    let weights = match get_option(command, "weights").and_then(|v| v.as_str()) {
+        Some(weights) => parse_weights(weights)?,
+        None => HashMap::new(),
+    };
+    let ties = match get_option(command, "ties").and_then(|v| v.as_str()) {
+        Some("first") => TiePolicy::First,
+        Some("runoff") => TiePolicy::Runoff,
+        _ => TiePolicy::Report,
+    };
+    let open_additions = get_option(command, "additions")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let pin = get_option(command, "pin")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let important = get_option(command, "important")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
 
     // poll data is stored in a static to be accessed for voting and cleanup
     let poll_data = PollData {
-        start_time: Instant::now(),
-        options: options.iter().copied().map(String::from).collect(),
+        start_time,
+        end_time,
+        scheduled: scheduled.is_some(),
+        reminded: false,
+        pinned: pin,
+        creator: command.user.id,
+        guild_id: command.guild_id,
+        channel_id: command.channel_id,
+        message_id: MessageId::default(),
+        closed: false,
+        title,
+        image,
+        options,
+        kind,
+        anonymous,
+        hide_results,
+        thread_results,
+        quorum,
+        target_votes,
+        abstain,
+        role,
+        eligible: None,
+        ties,
+        sort,
+        colors: settings.colors,
+        open_additions,
+        case_sensitive,
+        verified_only,
+        ping,
+        locale: command.locale.clone(),
+        caps,
+        weights,
+        voter_weights: HashMap::new(),
+        recovered_tallies: HashMap::new(),
         votes: HashMap::new(),
+        tallies: HashMap::new(),
+        weighted_tallies: HashMap::new(),
     };
+    if let Some(option) = poll_data
+        .options
+        .iter()
+        .find(|option| poll_data.is_reserved(option_id(option)))
+    {
+        anyhow::bail!(PollError::ReservedOption(option.clone()));
+    }
 
-    // respond with poll
-    command
+    if poll_data.scheduled {
+        // the confirmation shows a preview, so scheduled polls don't need to be confirmed
+        let preview = create_embed(&poll_data);
+        let start_secs = start_time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let content = i18n::format(
+            &poll_data.locale,
+            "scheduled",
+            &[("start", &format!("<t:{start_secs}:f>"))],
+        );
+        let button = create_unschedule_button(&poll_data.locale);
+        POLLS
+            .shard(command.id)
+            .write()
+            .await
+            .insert(command.id, poll_data);
+        evict(&ctx.http, config).await;
+        command
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|response_data| {
+                        response_data
+                            .content(content)
+                            .set_embed(preview)
+                            .components(|c| c.create_action_row(|row| row.add_button(button)))
+                            .ephemeral(true)
+                    })
+            })
+            .await
+            .context("failed to create response")?;
+    } else if important {
+        // the poll is posted once the creator confirms the preview
+        let preview = create_embed(&poll_data);
+        let buttons = create_draft_buttons(&poll_data.locale);
+        DRAFTS.lock().await.insert(
+            command.id,
+            Draft {
+                poll_data,
+                token: command.token.clone(),
+                created: Instant::now(),
+                config: *config,
+                duration,
+            },
+        );
+        command
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|response_data| {
+                        response_data
+                            .set_embed(preview)
+                            .components(|c| c.set_action_rows(vec![buttons]))
+                            .ephemeral(true)
+                    })
+            })
+            .await
+            .context("failed to create response")?;
+    } else {
+        post(ctx, command, poll_data, config).await?;
+    }
+
+    // let the creator know why their poll has fewer options than they entered
+    if duplicates > 0 {
+        command
+            .create_followup_message(&ctx.http, |followup| {
+                followup
+                    .content(if duplicates == 1 {
+                        i18n::text(&command.locale, "duplicates_removed_one").to_string()
+                    } else {
+                        i18n::format(
+                            &command.locale,
+                            "duplicates_removed",
+                            &[("count", &duplicates)],
+                        )
+                    })
+                    .ephemeral(true)
+            })
+            .await
+            .context("failed to create followup message")?;
+    }
+    Ok(())
+}
+
+/// Posts the poll as the response to the command
+async fn post(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    mut poll_data: PollData,
+    config: &Config,
+) -> anyhow::Result<()> {
+    // acknowledge the command before waiting for the lock in case the bot is busy, which is
+    // done only after validating the poll since errors can't be shown ephemerally afterwards
+    let res = command
         .create_interaction_response(&ctx.http, |response| {
+            response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+        })
+        .await
+        .context("failed to create response");
+    match res {
+        // the existing response is replaced with the poll
+        Ok(()) => {}
+        Err(err) if discord_error_code(&err) == Some(ALREADY_ACKNOWLEDGED) => {}
+        Err(err) if discord_error_code(&err) == Some(UNKNOWN_INTERACTION) => {
+            // Discord shows the user that the command failed
+            tracing::warn!("Poll command expired before the poll was posted");
+            return Ok(());
+        }
+        Err(err) => return Err(err),
+    }
+    // counting the members can take a while, so it's done once the command is acknowledged
+    poll_data.eligible = count_eligible(&ctx.http, &poll_data).await;
+    let embed = create_embed(&poll_data);
+    let components = create_vote_components(&poll_data);
+    let ping = create_ping(&poll_data);
+    let ping_role = poll_data.ping;
+    let (guild_id, channel_id) = (poll_data.guild_id, poll_data.channel_id);
+    let locale = poll_data.locale.clone();
+    let message = async {
+        command
+            .edit_original_interaction_response(&ctx.http, |response| {
+                response
+                    .content(ping)
+                    .set_embed(embed)
+                    .components(|c| c.set_action_rows(components))
+                    // only the role is mentioned, even if the title mentions @everyone
+                    .allowed_mentions(|mentions| mentions.empty_parse().roles(ping_role))
+            })
+            .await
+            .context("failed to edit response")
+    };
+    let message_id = publish(&ctx.http, command.id, poll_data, config, message).await?;
+
+    // the link is handy for sharing the poll, and only shown to the creator to avoid clutter
+    let link = message_id.link(channel_id, guild_id);
+    let res = command
+        .create_followup_message(&ctx.http, |followup| {
+            followup
+                .content(i18n::format(&locale, "posted_link", &[("link", &link)]))
+                .ephemeral(true)
+        })
+        .await;
+    if let Err(err) = res {
+        tracing::warn!("Failed to send the link to poll {message_id}: {err}");
+    }
+    Ok(())
+}
+
+/// Stores the poll and posts it with the message future, storing it first so that votes cast as
+/// soon as it appears can find it
+async fn publish(
+    http: &Http,
+    id: InteractionId,
+    poll_data: PollData,
+    config: &Config,
+    message: impl Future<Output = anyhow::Result<Message>>,
+) -> anyhow::Result<MessageId> {
+    let channel_id = poll_data.channel_id;
+    let pin = poll_data.pinned;
+    POLLS.shard(id).write().await.insert(id, poll_data);
+
+    let message = message.await;
+    let mut lock = POLLS.shard(id).write().await;
+    let (message_id, posted) = match message {
+        // the message ID is only known once the poll has been posted
+        Ok(message) => {
+            let posted = lock.get_mut(&id).map(|poll_data| {
+                poll_data.message_id = message.id;
+                poll_data.clone()
+            });
+            (message.id, posted)
+        }
+        Err(err) => {
+            lock.remove(&id);
+            return Err(err);
+        }
+    };
+    drop(lock);
+    // written once the poll is unlocked, so that votes on it don't wait for the database
+    if let Some(poll_data) = posted {
+        history::record_poll(id, &poll_data).await;
+    }
+    if pin {
+        if let Err(err) = channel_id.pin(http, message_id).await {
+            // usually caused by the bot missing the Manage Messages permission
+            tracing::warn!("Failed to pin poll {message_id}: {err:#}");
+            if let Some(poll_data) = POLLS.shard(id).write().await.get_mut(&id) {
+                poll_data.pinned = false;
+            }
+        }
+    }
+    POLLS_CREATED.fetch_add(1, Ordering::Relaxed);
+    if !config.cooldown.is_zero() {
+        COOLDOWNS
+            .lock()
+            .await
+            .insert(channel_id, Instant::now() + config.cooldown);
+    }
+    evict(http, config).await;
+    Ok(message_id)
+}
+
+/// Closes the polls that started first if there are more than the maximum number of polls, so
+/// that the number of polls in memory stays bounded even when many are started at once
+async fn evict(http: &Http, config: &Config) {
+    let Some(max_polls) = config.max_active_polls else {
+        return;
+    };
+    for (id, mut poll_data) in POLLS.evict_oldest(max_polls).await {
+        tracing::warn!(
+            "Evicting poll {id} in channel {} to stay within the maximum of {max_polls} polls",
+            poll_data.channel_id
+        );
+        log_outcome(id, &poll_data);
+        // polls closed early and scheduled polls don't have a message to update
+        if poll_data.closed || poll_data.scheduled {
+            continue;
+        }
+        if let Err(err) = finish(http, &mut poll_data).await {
+            tracing::error!("Failed to finish poll {}: {err:#}", poll_data.message_id);
+        }
+    }
+}
+
+/// Posts or cancels a draft poll depending on which of the preview's buttons was clicked
+async fn confirm(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    message_interaction: &MessageInteraction,
+) -> anyhow::Result<()> {
+    let draft = DRAFTS
+        .lock()
+        .await
+        .remove(&message_interaction.id)
+        .ok_or(PollError::DraftExpired)?;
+    let post = interaction.data.custom_id == POST_DRAFT_ID;
+    interaction
+        .create_interaction_response(ctx, |response| {
             response
-                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .kind(InteractionResponseType::UpdateMessage)
                 .interaction_response_data(|response_data| {
                     response_data
-                        .content(create_content(&poll_data))
-                        .components(|components| {
-                            // create voting buttons
-                            let mut row = CreateActionRow::default();
-                            for option in options.iter().copied() {
-                                row.add_button(create_vote_button(option, 0));
-                            }
-                            components.add_action_row(row)
-                        })
+                        .content(i18n::text(
+                            &interaction.locale,
+                            if post {
+                                "draft_posted"
+                            } else {
+                                "draft_cancelled"
+                            },
+                        ))
+                        .components(|c| c.set_action_rows(vec![]))
                 })
         })
         .await
         .context("failed to create response")?;
+    if !post {
+        return Ok(());
+    }
 
-    // on success, store poll data
-    let mut lock = POLLS.write().await;
-    lock.insert(command.id, poll_data);
+    let Draft {
+        mut poll_data,
+        token,
+        config,
+        duration,
+        ..
+    } = draft;
+    poll_data.eligible = count_eligible(&ctx.http, &poll_data).await;
+    // durations start once the poll is posted
+    poll_data.start_time = SystemTime::now();
+    if let Some(duration) = duration {
+        poll_data.end_time = poll_data.start_time + duration;
+    }
+    // posted as a followup to the poll command so that votes are handled like for other polls
+    let mut followup = CreateInteractionResponseFollowup::default();
+    followup
+        .content(create_ping(&poll_data))
+        .set_embed(create_embed(&poll_data))
+        .components(|c| c.set_action_rows(create_vote_components(&poll_data)))
+        .allowed_mentions(|mentions| mentions.empty_parse().roles(poll_data.ping));
+    let followup = Value::from(json::hashmap_to_json_map(followup.0));
+    let message = async move {
+        ctx.http
+            .create_followup_message(&token, &followup)
+            .await
+            .context("failed to post poll")
+    };
+    publish(
+        &ctx.http,
+        message_interaction.id,
+        poll_data,
+        &config,
+        message,
+    )
+    .await?;
     Ok(())
 }
 
-pub async fn vote(
+/// Counts the members who have the role needed to vote in the poll, for showing the turnout.
+/// Requires the bot to have the server members intent, and gives up in large servers
+async fn count_eligible(http: &Http, poll_data: &PollData) -> Option<u32> {
+    let (guild_id, role) = (poll_data.guild_id?, poll_data.role?);
+    let mut eligible = 0;
+    let mut after = None;
+    for _ in 0..MAX_MEMBER_PAGES {
+        let members = match guild_id.members(http, Some(MEMBERS_PER_PAGE), after).await {
+            Ok(members) => members,
+            Err(err) => {
+                tracing::warn!("Failed to count the members with role {role}: {err}");
+                return None;
+            }
+        };
+        eligible += members
+            .iter()
+            .filter(|member| member.roles.contains(&role))
+            .count() as u32;
+        if (members.len() as u64) < MEMBERS_PER_PAGE {
+            return Some(eligible);
+        }
+        after = members.last().map(|member| member.user.id);
+    }
+    None
+}
+
+/// Cancels the scheduled poll whose confirmation's button was clicked
+async fn unschedule(
     ctx: &Context,
     interaction: &MessageComponentInteraction,
     message_interaction: &MessageInteraction,
 ) -> anyhow::Result<()> {
-    // save the user's vote in the poll data
-    let mut lock = POLLS.write().await;
-
-    let poll_data = lock
-        .get_mut(&message_interaction.id)
-        .context("unexpected interaction id")?;
-    let user_id = interaction
-        .member
-        .as_ref()
-        .context("missing member")?
-        .user
-        .id;
-    poll_data
-        .votes
-        .insert(user_id, interaction.data.custom_id.clone());
-
-    // create updated buttons
-    let mut row = CreateActionRow::default();
-    // the first (and only) action row should contain only the voting buttons
-    let button_row = interaction
-        .message
-        .components
-        .first()
-        .context("missing action row")?;
-    for component in button_row.components.iter() {
-        if let ActionRowComponent::Button(b) = component {
-            let custom_id = b.custom_id.as_ref().context("missing custom id")?;
-            let votes = poll_data.votes_for(custom_id);
-            row.add_button(create_vote_button(custom_id, votes));
-        } else {
-            anyhow::bail!("unexpected component");
-        }
+    let mut lock = POLLS.shard(message_interaction.id).write().await;
+    // the poll is no longer scheduled once it has been posted
+    let scheduled = lock
+        .get(&message_interaction.id)
+        .map_or(false, |poll_data| poll_data.scheduled);
+    if !scheduled {
+        anyhow::bail!(PollError::PollNotFound);
     }
+    lock.remove(&message_interaction.id);
+    drop(lock);
 
-    // update the message
     interaction
         .create_interaction_response(ctx, |response| {
             response
                 .kind(InteractionResponseType::UpdateMessage)
                 .interaction_response_data(|response_data| {
                     response_data
-                        .content(create_content(&poll_data))
-                        .components(|c| c.set_action_rows(vec![row]))
+                        .content(i18n::text(&interaction.locale, "unscheduled"))
+                        .set_embeds(vec![])
+                        .components(|c| c.set_action_rows(vec![]))
                 })
         })
-        .await?;
+        .await
+        .context("failed to create response")?;
     Ok(())
 }
 
-/// Periodically removes old poll data from memory
-pub async fn cleaner(interval: Duration, poll_duration: Duration) {
-    let mut interval = tokio::time::interval(interval);
-    loop {
-        interval.tick().await;
-        let mut lock = POLLS.write().await;
-        let mut remove = vec![];
-        for (key, val) in lock.iter() {
-            if val.start_time.elapsed() > poll_duration {
-                remove.push(*key);
-            }
+/// Posts the scheduled poll in its channel now that it has started
+async fn post_scheduled(http: &Http, id: InteractionId) -> anyhow::Result<()> {
+    // the requests are made with a copy of the poll, so that the shard isn't locked meanwhile
+    let lock = POLLS.shard(id).read().await;
+    let Some(mut poll_data) = lock.get(&id).cloned() else {
+        return Ok(());
+    };
+    drop(lock);
+
+    poll_data.eligible = count_eligible(http, &poll_data).await;
+    // posted as a regular message since the command can't be responded to this late, so the
+    // message has no interaction and votes on it are matched to the poll by the message's ID
+    let message = poll_data
+        .channel_id
+        .send_message(http, |message| {
+            message
+                .content(create_ping(&poll_data))
+                .set_embed(create_embed(&poll_data))
+                .components(|c| c.set_action_rows(create_vote_components(&poll_data)))
+                .allowed_mentions(|mentions| mentions.empty_parse().roles(poll_data.ping))
+        })
+        .await;
+    let message = match message {
+        Ok(message) => message,
+        Err(err) => {
+            // dropped so that it isn't posted again every time the cleaner runs
+            POLLS.shard(id).write().await.remove(&id);
+            return Err(err).context("failed to post scheduled poll");
         }
-        for target in remove {
-            lock.remove(&target);
+    };
+    if poll_data.pinned {
+        if let Err(err) = poll_data.channel_id.pin(http, message.id).await {
+            tracing::warn!("Failed to pin poll {}: {err:#}", message.id);
+            poll_data.pinned = false;
         }
     }
+
+    let mut lock = POLLS.shard(id).write().await;
+    let Some(stored) = lock.get_mut(&id) else {
+        drop(lock);
+        // the poll was cancelled while it was being posted
+        if let Err(err) = message.delete(http).await {
+            tracing::warn!("Failed to delete cancelled poll {}: {err:#}", message.id);
+        }
+        return Ok(());
+    };
+    stored.eligible = poll_data.eligible;
+    stored.pinned = poll_data.pinned;
+    stored.message_id = message.id;
+    stored.scheduled = false;
+    drop(lock);
+
+    poll_data.message_id = message.id;
+    poll_data.scheduled = false;
+    POLLS_CREATED.fetch_add(1, Ordering::Relaxed);
+    history::record_poll(id, &poll_data).await;
+    Ok(())
 }
 
-fn create_content(poll_data: &PollData) -> String {
-    format!("Vote:\n{}", poll_data.options.join(","))
+/// The interaction of a poll message, including the polls posted by the cleaner without one and
+/// the voters' panels, which are responses to a button rather than the command
+pub async fn message_interaction(message: &Message, custom_id: &str) -> Option<MessageInteraction> {
+    let id = match parse_switch_id(custom_id) {
+        Some((id, _)) => id,
+        None => {
+            if let Some(interaction) = &message.interaction {
+                return Some(interaction.clone());
+            }
+            POLLS.find_by_message(message.id).await?
+        }
+    };
+    let creator = POLLS.shard(id).read().await.get(&id)?.creator;
+    let mut user = User::default();
+    user.id = creator;
+    Some(MessageInteraction {
+        id,
+        kind: InteractionType::ApplicationCommand,
+        name: COMMAND.to_string(),
+        user,
+    })
 }
 
-fn create_vote_button(option: &str, votes: u32) -> CreateButton {
-    let mut button = CreateButton::default();
-    button
-        .custom_id(option)
-        .label(format!("{}: {}", option, votes))
-        .style(ButtonStyle::Primary);
-    button
+async fn vote(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    message_interaction: &MessageInteraction,
+    config: &Config,
+) -> anyhow::Result<()> {
+    // interactions outside of guilds, such as in DMs, only have the user
+    let user_id = match &interaction.member {
+        Some(member) => member.user.id,
+        None => interaction.user.id,
+    };
+    // clicks in quick succession are acknowledged without voting or updating the message again
+    let now = Instant::now();
+    let previous = LAST_VOTES
+        .lock()
+        .await
+        .get(&(message_interaction.id, user_id))
+        .copied();
+    if previous.map_or(false, |previous| now - previous < VOTE_DEBOUNCE) {
+        interaction
+            .create_interaction_response(ctx, |response| {
+                response.kind(InteractionResponseType::DeferredUpdateMessage)
+            })
+            .await
+            .context("failed to create response")?;
+        return Ok(());
+    }
+
+    // save the user's vote in the poll data
+    let mut lock = POLLS.shard(message_interaction.id).write().await;
+
+    let poll_data = lock
+        .get_mut(&message_interaction.id)
+        .ok_or(PollError::PollEnded)?;
+    let weight = check_voter(poll_data, interaction, config)?;
+    if interaction.data.component_type == ComponentType::SelectMenu {
+        let values = interaction
+            .data
+            .values
+            .iter()
+            .map(|value| poll_data.resolve_component_id(value).to_string())
+            .collect::<Vec<_>>();
+        poll_data.select(user_id, &values, weight)?;
+    } else {
+        // buttons on polls from before the prefix was added don't have it
+        let custom_id = &interaction.data.custom_id;
+        let id = custom_id.strip_prefix(OPTION_PREFIX).unwrap_or(custom_id);
+        let vote_id = poll_data.resolve_component_id(id).to_string();
+        poll_data.vote(user_id, &vote_id, weight)?;
+    }
+    // only applied votes count for the debounce, so ignored clicks don't extend it
+    LAST_VOTES
+        .lock()
+        .await
+        .insert((message_interaction.id, user_id), now);
+    let (votes, update) = record_vote(poll_data, user_id, config.vote_batch.is_some());
+
+    let res = async {
+        // the message is updated by the flusher along with the other votes cast in the meantime,
+        // except when the poll closes, which is shown right away
+        if update == VoteUpdate::Batched {
+            OUTDATED.lock().await.insert(message_interaction.id);
+            let res = interaction
+                .create_interaction_response(ctx, |response| {
+                    response.kind(InteractionResponseType::DeferredUpdateMessage)
+                })
+                .await
+                .context("failed to create response");
+            match res {
+                Err(err) if is_stale_interaction(&err) => {
+                    tracing::warn!("Vote interaction expired before it was responded to");
+                }
+                res => res?,
+            }
+            return Ok(());
+        }
+
+        // update the message
+        let res = interaction
+            .create_interaction_response(ctx, |response| {
+                response
+                    .kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|response_data| {
+                        // the content is replaced on polls posted before they had embeds
+                        response_data
+                            .content(create_ping(poll_data))
+                            .set_embed(create_embed(poll_data))
+                            .components(|c| c.set_action_rows(create_vote_components(poll_data)))
+                    })
+            })
+            .await
+            .context("failed to create response");
+        match res {
+            // the vote was counted, so the message can still be updated without the interaction
+            Err(err) if is_stale_interaction(&err) => {
+                tracing::warn!("Vote interaction expired before it was responded to");
+                poll_data
+                    .channel_id
+                    .edit_message(ctx, poll_data.message_id, |message| {
+                        message
+                            .content(create_ping(poll_data))
+                            .set_embed(create_embed(poll_data))
+                            .components(|c| c.set_action_rows(create_vote_components(poll_data)))
+                    })
+                    .await
+                    .context("failed to edit poll message")?;
+            }
+            res => res?,
+        }
+        if update == VoteUpdate::Closed {
+            wrap_up(&ctx.http, poll_data).await?;
+        }
+        Ok(())
+    }
+    .await;
+    // written once the poll is unlocked, so that other votes on it don't wait for the database
+    drop(lock);
+    history::record_vote(message_interaction.id, user_id, &votes, weight).await;
+    res
+}
+
+/// Checks that the user can vote in the poll, returning the weight of their vote
+fn check_voter(
+    poll_data: &PollData,
+    interaction: &MessageComponentInteraction,
+    config: &Config,
+) -> anyhow::Result<u32> {
+    if poll_data.closed {
+        anyhow::bail!(PollError::PollClosed);
+    }
+    check_role(poll_data, interaction.member.as_ref())?;
+    check_verified(
+        poll_data,
+        &interaction.user,
+        interaction.member.as_ref(),
+        config,
+    )?;
+    // the weight is kept with the votes, so later role changes don't affect them
+    let weight = match &interaction.member {
+        Some(member) => poll_data.weight_for_roles(&member.roles),
+        None => 1,
+    };
+    Ok(weight)
+}
+
+/// How the poll's message is updated after a vote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VoteUpdate {
+    // by the flusher, along with the other votes cast in the meantime
+    Batched,
+    // right away
+    Immediate,
+    // right away, and the results are announced since the vote closed the poll
+    Closed,
+}
+
+/// Counts the user's changed vote, closing the poll if enough people have voted. Returns the
+/// user's votes to be recorded in the history, and how the poll's message is to be updated
+fn record_vote(
+    poll_data: &mut PollData,
+    user_id: UserId,
+    batched: bool,
+) -> (Vec<String>, VoteUpdate) {
+    VOTES_CAST.fetch_add(1, Ordering::Relaxed);
+    let votes = poll_data.votes.get(&user_id).cloned().unwrap_or_default();
+    // the poll closes as soon as enough people have voted, like when the time runs out
+    let target_reached = poll_data
+        .target_votes
+        .map_or(false, |target| poll_data.votes.len() >= target as usize);
+    let update = if target_reached {
+        poll_data.closed = true;
+        VoteUpdate::Closed
+    } else if batched {
+        VoteUpdate::Batched
+    } else {
+        VoteUpdate::Immediate
+    };
+    (votes, update)
+}
+
+/// Shows the user a panel only they can see with their current vote highlighted and buttons for
+/// changing it, since the poll's own buttons look the same for everyone
+async fn show_my_vote(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    message_interaction: &MessageInteraction,
+) -> anyhow::Result<()> {
+    let user_id = match &interaction.member {
+        Some(member) => member.user.id,
+        None => interaction.user.id,
+    };
+    let lock = POLLS.shard(message_interaction.id).read().await;
+    let poll_data = lock
+        .get(&message_interaction.id)
+        .ok_or(PollError::PollEnded)?;
+    if poll_data.closed {
+        anyhow::bail!(PollError::PollClosed);
+    }
+    interaction
+        .create_interaction_response(ctx, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|response_data| {
+                    response_data
+                        .ephemeral(true)
+                        .content(create_my_vote(poll_data, user_id, &interaction.locale))
+                        .components(|c| {
+                            c.set_action_rows(create_my_vote_components(
+                                poll_data,
+                                message_interaction.id,
+                                user_id,
+                                &interaction.locale,
+                            ))
+                        })
+                })
+        })
+        .await
+        .context("failed to create response")?;
+    Ok(())
+}
+
+/// Changes the user's vote from their panel, updating both the poll and the panel
+async fn switch_vote(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    message_interaction: &MessageInteraction,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let user_id = match &interaction.member {
+        Some(member) => member.user.id,
+        None => interaction.user.id,
+    };
+    let (_, choice) =
+        parse_switch_id(&interaction.data.custom_id).context("invalid panel button")?;
+    let id = message_interaction.id;
+    let mut lock = POLLS.shard(id).write().await;
+    let poll_data = lock.get_mut(&id).ok_or(PollError::PollEnded)?;
+    let weight = check_voter(poll_data, interaction, config)?;
+    let vote_id = match choice {
+        ABSTAIN_ID => ABSTAIN_ID.to_string(),
+        index => index
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| poll_data.options.get(index))
+            .map(|option| option_id(option).to_string())
+            .ok_or_else(|| PollError::OptionNotFound(index.to_string()))?,
+    };
+    poll_data.vote(user_id, &vote_id, weight)?;
+    let (votes, update) = record_vote(poll_data, user_id, config.vote_batch.is_some());
+
+    let res = async {
+        // the poll's message is updated like after a vote on it, by the flusher if votes are
+        // batched
+        if update == VoteUpdate::Batched {
+            OUTDATED.lock().await.insert(id);
+        } else {
+            poll_data
+                .channel_id
+                .edit_message(ctx, poll_data.message_id, |message| {
+                    message
+                        .content(create_ping(poll_data))
+                        .set_embed(create_embed(poll_data))
+                        .components(|c| c.set_action_rows(create_vote_components(poll_data)))
+                })
+                .await
+                .context("failed to edit poll message")?;
+        }
+        interaction
+            .create_interaction_response(ctx, |response| {
+                response
+                    .kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|response_data| {
+                        response_data
+                            .content(create_my_vote(poll_data, user_id, &interaction.locale))
+                            .components(|c| {
+                                c.set_action_rows(create_my_vote_components(
+                                    poll_data,
+                                    id,
+                                    user_id,
+                                    &interaction.locale,
+                                ))
+                            })
+                    })
+            })
+            .await
+            .context("failed to create response")?;
+        if update == VoteUpdate::Closed {
+            wrap_up(&ctx.http, poll_data).await?;
+        }
+        Ok(())
+    }
+    .await;
+    // written once the poll is unlocked, so that other votes on it don't wait for the database
+    drop(lock);
+    history::record_vote(id, user_id, &votes, weight).await;
+    res
+}
+
+/// Reads the poll's ID and the option's position or the abstain ID from a panel button's ID
+fn parse_switch_id(custom_id: &str) -> Option<(InteractionId, &str)> {
+    let (id, choice) = custom_id.strip_prefix(SWITCH_PREFIX)?.split_once(':')?;
+    Some((InteractionId(id.parse().ok()?), choice))
+}
+
+/// Opens a modal for a voter to enter a new option in
+async fn prompt_option(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    message_interaction: &MessageInteraction,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let lock = POLLS.shard(message_interaction.id).read().await;
+    let poll_data = lock
+        .get(&message_interaction.id)
+        .ok_or(PollError::PollEnded)?;
+    if poll_data.closed {
+        anyhow::bail!(PollError::PollClosed);
+    }
+    check_role(poll_data, interaction.member.as_ref())?;
+    check_verified(
+        poll_data,
+        &interaction.user,
+        interaction.member.as_ref(),
+        config,
+    )?;
+    drop(lock);
+
+    interaction
+        .create_interaction_response(ctx, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|response_data| {
+                    response_data
+                        .custom_id(ADD_OPTION_ID)
+                        .title(i18n::text(&interaction.locale, "add_option_title"))
+                        .components(|c| {
+                            c.create_action_row(|row| {
+                                row.create_input_text(|input| {
+                                    input
+                                        .custom_id(ADD_OPTION_INPUT_ID)
+                                        .label(i18n::text(&interaction.locale, "add_option_label"))
+                                        .style(InputTextStyle::Short)
+                                        .max_length(MAX_OPTION_LENGTH as u64)
+                                        .required(true)
+                                })
+                            })
+                        })
+                })
+        })
+        .await
+        .context("failed to create response")?;
+    Ok(())
+}
+
+/// Adds the option entered in the modal to the poll
+async fn add_option(
+    ctx: &Context,
+    interaction: &ModalSubmitInteraction,
+    message_interaction: &MessageInteraction,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let option = interaction
+        .data
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) if input.custom_id == ADD_OPTION_INPUT_ID => {
+                Some(input.value.trim())
+            }
+            _ => None,
+        })
+        .context("missing option input")?;
+    if option.is_empty() {
+        anyhow::bail!(PollError::NoOptions);
+    }
+    if !is_visible(option_id(option)) {
+        anyhow::bail!(PollError::InvisibleOption);
+    }
+    if option_id(option).chars().count() > MAX_OPTION_LENGTH {
+        anyhow::bail!(PollError::OptionTooLong(option.to_string()));
+    }
+
+    let mut lock = POLLS.shard(message_interaction.id).write().await;
+    let poll_data = lock
+        .get_mut(&message_interaction.id)
+        .ok_or(PollError::PollEnded)?;
+    if poll_data.closed || !poll_data.open_additions {
+        anyhow::bail!(PollError::PollClosed);
+    }
+    check_role(poll_data, interaction.member.as_ref())?;
+    check_verified(
+        poll_data,
+        &interaction.user,
+        interaction.member.as_ref(),
+        config,
+    )?;
+    // options are identified by their text, so the new text has to be unique
+    if poll_data.similar_option(option).is_some() {
+        anyhow::bail!(PollError::OptionExists(option.to_string()));
+    }
+    if poll_data.is_reserved(option_id(option)) {
+        anyhow::bail!(PollError::ReservedOption(option.to_string()));
+    }
+    let max_options = settings::get(poll_data.guild_id)
+        .await
+        .apply(config)
+        .max_options
+        .min(MAX_OPTIONS - usize::from(poll_data.abstain));
+    if poll_data.options.len() >= max_options {
+        anyhow::bail!(PollError::TooManyOptions(
+            poll_data.options.len() + 1,
+            max_options
+        ));
+    }
+    poll_data.options.push(option.to_string());
+
+    interaction
+        .create_interaction_response(ctx, |response| {
+            response
+                .kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|response_data| {
+                    response_data
+                        .content(create_ping(poll_data))
+                        .set_embed(create_embed(poll_data))
+                        .components(|c| c.set_action_rows(create_vote_components(poll_data)))
+                })
+        })
+        .await
+        .context("failed to create response")?;
+    Ok(())
+}
+
+/// Checks that the member has the role needed to take part in the poll
+fn check_role(poll_data: &PollData, member: Option<&Member>) -> Result<(), PollError> {
+    if let Some(role) = poll_data.role {
+        // members are missing outside of guilds, where nobody can have the role
+        let has_role = member.map_or(false, |member| member.roles.contains(&role));
+        if !has_role {
+            return Err(PollError::MissingRole);
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the user's account is old enough and has passed the server's membership screening
+/// if the poll only allows verified voters, which makes stuffing the poll with new accounts harder
+fn check_verified(
+    poll_data: &PollData,
+    user: &User,
+    member: Option<&Member>,
+    config: &Config,
+) -> Result<(), PollError> {
+    if !poll_data.verified_only {
+        return Ok(());
+    }
+    // the account's creation time is part of its ID
+    let created = user.id.created_at().unix_timestamp();
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let old_enough = now - created >= config.min_account_age.as_secs() as i64;
+    let screened = member.map_or(true, |member| !member.pending);
+    if !old_enough || !screened {
+        // rounded up so that a minimum age of less than a day isn't shown as 0 days
+        let days = (config.min_account_age.as_secs() + 60 * 60 * 24 - 1) / (60 * 60 * 24);
+        return Err(PollError::Unverified(days));
+    }
+    Ok(())
+}
+
+/// Starts a new poll like an earlier one, which is recovered from its message if it has already
+/// been removed
+async fn clone_poll(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let poll = get_option(command, "poll")
+        .context("missing poll")?
+        .as_str()
+        .context("invalid poll value")?;
+    let message_id = get_poll_message_id(command)?;
+    check_can_start(command, config).await?;
+
+    let mut poll_data = None;
+    if let Some(id) = POLLS.find_by_message(message_id).await {
+        if let Some(original) = POLLS.shard(id).read().await.get(&id) {
+            poll_data = Some(original.rerun(command));
+        }
+    }
+    let poll_data = match poll_data {
+        Some(poll_data) => poll_data,
+        None => {
+            // links include the channel, otherwise the poll has to be in this channel
+            let channel_id = poll
+                .trim()
+                .rsplit('/')
+                .nth(1)
+                .and_then(|id| id.parse().ok())
+                .map_or(command.channel_id, ChannelId);
+            let message = channel_id
+                .message(&ctx.http, message_id)
+                .await
+                .map_err(|_| PollError::PollNotFound)?;
+            let (_, original) = recover::recover_poll(&message).ok_or(PollError::PollNotFound)?;
+            original.rerun(command)
+        }
+    };
+    post(ctx, command, poll_data, config).await
+}
+
+async fn close(ctx: &Context, command: &ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let message_id = get_poll_message_id(command)?;
+
+    let id = POLLS
+        .find_by_message(message_id)
+        .await
+        .ok_or(PollError::PollNotFound)?;
+    let mut lock = POLLS.shard(id).write().await;
+    let poll_data = lock.get_mut(&id).ok_or(PollError::PollNotFound)?;
+    if poll_data.creator != command.user.id {
+        anyhow::bail!(PollError::NotCreator);
+    }
+    if poll_data.closed {
+        anyhow::bail!(PollError::PollClosed);
+    }
+    finish(&ctx.http, poll_data).await?;
+    drop(lock);
+
+    respond_ephemeral(ctx, command, i18n::text(&command.locale, "poll_closed")).await
+}
+
+/// Makes another user the creator of the poll, such as when the creator is leaving. Members who
+/// can manage the server can also transfer polls in it, for when the creator has already left
+async fn transfer(ctx: &Context, command: &ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let message_id = get_poll_message_id(command)?;
+    // user options are sent as the user's ID in a string
+    let user_id = get_option(command, "user")
+        .context("missing user")?
+        .as_str()
+        .context("invalid user value")?
+        .parse()
+        .map(UserId)
+        .context("invalid user value")?;
+
+    let id = POLLS
+        .find_by_message(message_id)
+        .await
+        .ok_or(PollError::PollNotFound)?;
+    let mut lock = POLLS.shard(id).write().await;
+    let poll_data = lock.get_mut(&id).ok_or(PollError::PollNotFound)?;
+    let admin = poll_data.guild_id.is_some()
+        && poll_data.guild_id == command.guild_id
+        && command
+            .member
+            .as_ref()
+            .and_then(|member| member.permissions)
+            .map_or(false, |permissions| permissions.manage_guild());
+    if poll_data.creator != command.user.id && !admin {
+        anyhow::bail!(PollError::NotCreator);
+    }
+    if poll_data.closed {
+        anyhow::bail!(PollError::PollClosed);
+    }
+    poll_data.creator = user_id;
+    drop(lock);
+
+    let reply = i18n::format(&command.locale, "transferred", &[("user", &user_id)]);
+    respond_ephemeral(ctx, command, &reply).await
+}
+
+async fn edit(ctx: &Context, command: &ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let message_id = get_poll_message_id(command)?;
+    let old = get_option(command, "option")
+        .context("missing option")?
+        .as_str()
+        .context("invalid option value")?
+        .trim();
+    let new = get_option(command, "new")
+        .context("missing new")?
+        .as_str()
+        .context("invalid new value")?
+        .trim();
+    if new.is_empty() {
+        anyhow::bail!(PollError::NoOptions);
+    }
+    if !is_visible(option_id(new)) {
+        anyhow::bail!(PollError::InvisibleOption);
+    }
+    if option_id(new).chars().count() > MAX_OPTION_LENGTH {
+        anyhow::bail!(PollError::OptionTooLong(new.to_string()));
+    }
+
+    let id = POLLS
+        .find_by_message(message_id)
+        .await
+        .ok_or(PollError::PollNotFound)?;
+    let mut lock = POLLS.shard(id).write().await;
+    let poll_data = lock.get_mut(&id).ok_or(PollError::PollNotFound)?;
+    if poll_data.creator != command.user.id {
+        anyhow::bail!(PollError::NotCreator);
+    }
+    if poll_data.closed {
+        anyhow::bail!(PollError::PollClosed);
+    }
+    // the option can be given with or without its emoji
+    let index = poll_data
+        .options
+        .iter()
+        .position(|option| option == old || option_id(option) == option_id(old))
+        .ok_or_else(|| PollError::OptionNotFound(old.to_string()))?;
+    // options are identified by their text, so the new text has to be unique
+    if matches!(poll_data.similar_option(new), Some(i) if i != index) {
+        anyhow::bail!(PollError::OptionExists(new.to_string()));
+    }
+    if poll_data.is_reserved(option_id(new)) {
+        anyhow::bail!(PollError::ReservedOption(new.to_string()));
+    }
+    poll_data.rename(index, new.to_string());
+    poll_data
+        .channel_id
+        .edit_message(&ctx.http, poll_data.message_id, |message| {
+            message
+                .content(create_ping(poll_data))
+                .set_embed(create_embed(poll_data))
+                .components(|c| c.set_action_rows(create_vote_components(poll_data)))
+        })
+        .await
+        .context("failed to edit poll message")?;
+    drop(lock);
+
+    let reply = i18n::format(&command.locale, "renamed", &[("old", &old), ("new", &new)]);
+    respond_ephemeral(ctx, command, &reply).await
+}
+
+async fn refresh(ctx: &Context, command: &ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let message_id = get_poll_message_id(command)?;
+
+    let id = POLLS
+        .find_by_message(message_id)
+        .await
+        .ok_or(PollError::PollNotFound)?;
+    let lock = POLLS.shard(id).read().await;
+    let poll_data = lock.get(&id).ok_or(PollError::PollNotFound)?;
+    if poll_data.creator != command.user.id {
+        anyhow::bail!(PollError::NotCreator);
+    }
+    if poll_data.closed {
+        anyhow::bail!(PollError::PollClosed);
+    }
+    // rebuilt from the poll data, so the message matches the votes even if it was changed
+    poll_data
+        .channel_id
+        .edit_message(&ctx.http, poll_data.message_id, |message| {
+            message
+                .content(create_ping(poll_data))
+                .set_embed(create_embed(poll_data))
+                .components(|c| c.set_action_rows(create_vote_components(poll_data)))
+        })
+        .await
+        .context("failed to edit poll message")?;
+    drop(lock);
+
+    respond_ephemeral(ctx, command, i18n::text(&command.locale, "poll_refreshed")).await
+}
+
+async fn extend(ctx: &Context, command: &ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let message_id = get_poll_message_id(command)?;
+    let duration = get_option(command, "duration")
+        .context("missing duration")?
+        .as_str()
+        .context("invalid duration value")?;
+    let duration = parse_duration(duration)?;
+
+    let id = POLLS
+        .find_by_message(message_id)
+        .await
+        .ok_or(PollError::PollNotFound)?;
+    let mut lock = POLLS.shard(id).write().await;
+    let poll_data = lock.get_mut(&id).ok_or(PollError::PollNotFound)?;
+    if poll_data.creator != command.user.id {
+        anyhow::bail!(PollError::NotCreator);
+    }
+    if poll_data.closed {
+        anyhow::bail!(PollError::PollClosed);
+    }
+    // the limit applies to the poll's whole lifetime, not just the extension
+    let end_time = poll_data.end_time + duration;
+    let lifetime = end_time
+        .duration_since(poll_data.start_time)
+        .unwrap_or_default();
+    if lifetime > MAX_DURATION {
+        anyhow::bail!(PollError::DurationTooLong);
+    }
+    poll_data.end_time = end_time;
+    // the reminder is sent again before the new end time
+    poll_data.reminded = false;
+    // show the new end time and that the poll isn't closing soon anymore
+    poll_data
+        .channel_id
+        .edit_message(&ctx.http, poll_data.message_id, |message| {
+            message.set_embed(create_embed(poll_data))
+        })
+        .await
+        .context("failed to edit poll message")?;
+    drop(lock);
+
+    let end_secs = end_time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let reply = i18n::format(&command.locale, "extended", &[("end", &end_secs)]);
+    respond_ephemeral(ctx, command, &reply).await
+}
+
+async fn my_vote(ctx: &Context, command: &ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let message_id = get_poll_message_id(command)?;
+
+    let id = POLLS
+        .find_by_message(message_id)
+        .await
+        .ok_or(PollError::PollNotFound)?;
+    let lock = POLLS.shard(id).read().await;
+    let poll_data = lock.get(&id).ok_or(PollError::PollNotFound)?;
+    // open polls can be voted in from the reply like from the panel opened from the poll
+    let components = if poll_data.closed {
+        vec![]
+    } else {
+        create_my_vote_components(poll_data, id, command.user.id, &command.locale)
+    };
+    command
+        .create_interaction_response(ctx, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|response_data| {
+                    response_data
+                        .ephemeral(true)
+                        .content(create_my_vote(poll_data, command.user.id, &command.locale))
+                        .components(|c| c.set_action_rows(components))
+                })
+        })
+        .await
+        .context("failed to create response")?;
+    Ok(())
+}
+
+async fn voters(ctx: &Context, command: &ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let message_id = get_poll_message_id(command)?;
+
+    let id = POLLS
+        .find_by_message(message_id)
+        .await
+        .ok_or(PollError::PollNotFound)?;
+    let lock = POLLS.shard(id).read().await;
+    let poll_data = lock.get(&id).ok_or(PollError::PollNotFound)?;
+    if poll_data.anonymous {
+        anyhow::bail!(PollError::Anonymous);
+    }
+    if poll_data.results_hidden() {
+        anyhow::bail!(PollError::ResultsHidden);
+    }
+    let voters = voters_by_option(poll_data, &command.locale);
+    drop(lock);
+
+    let reply = create_voters(&voters, &command.locale, |user_id| format!("<@{user_id}>"));
+    respond_ephemeral(ctx, command, &reply).await
+}
+
+/// Replies to the voters button with the names of the users who voted for each option
+async fn show_voters(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    message_interaction: &MessageInteraction,
+) -> anyhow::Result<()> {
+    let lock = POLLS.shard(message_interaction.id).read().await;
+    let poll_data = lock
+        .get(&message_interaction.id)
+        .ok_or(PollError::PollEnded)?;
+    if poll_data.anonymous {
+        anyhow::bail!(PollError::Anonymous);
+    }
+    if poll_data.results_hidden() {
+        anyhow::bail!(PollError::ResultsHidden);
+    }
+    let voters = voters_by_option(poll_data, &interaction.locale);
+    let user_ids = poll_data.votes.keys().copied().collect::<Vec<_>>();
+    drop(lock);
+
+    // fetching the names can take longer than Discord waits for a response
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|response_data| response_data.ephemeral(true))
+        })
+        .await
+        .context("failed to create response")?;
+    let mut names = HashMap::new();
+    for user_id in user_ids {
+        match user_id.to_user(&ctx.http).await {
+            Ok(user) => {
+                names.insert(user_id, user.name);
+            }
+            Err(err) => tracing::warn!("Failed to fetch user {user_id}: {err:#}"),
+        }
+    }
+    let reply = create_voters(&voters, &interaction.locale, |user_id| {
+        match names.get(&user_id) {
+            Some(name) => name.clone(),
+            None => format!("<@{user_id}>"),
+        }
+    });
+    interaction
+        .edit_original_interaction_response(&ctx.http, |response| {
+            response
+                .content(reply)
+                .allowed_mentions(|mentions| mentions.empty_parse())
+        })
+        .await
+        .context("failed to edit response")?;
+    Ok(())
+}
+
+/// The users who voted for each option, followed by the users who abstained
+fn voters_by_option(poll_data: &PollData, locale: &str) -> Vec<(String, Vec<UserId>)> {
+    let abstained = i18n::text(locale, "voters_abstained");
+    let abstain = poll_data.abstain.then_some((abstained, ABSTAIN_ID));
+    poll_data
+        .options
+        .iter()
+        .map(|option| (option.as_str(), option_id(option)))
+        .chain(abstain)
+        .map(|(option, vote_id)| {
+            let voters = poll_data
+                .votes
+                .iter()
+                .filter(|(_, votes)| votes.iter().any(|v| v == vote_id))
+                .map(|(user_id, _)| *user_id)
+                .collect();
+            (option.to_string(), voters)
+        })
+        .collect()
+}
+
+/// Lists the voters for each option, as far as they fit in a message
+fn create_voters(
+    voters: &[(String, Vec<UserId>)],
+    locale: &str,
+    name: impl Fn(UserId) -> String,
+) -> String {
+    let mut reply = String::new();
+    for (option, user_ids) in voters {
+        let line = if user_ids.is_empty() {
+            let none = i18n::text(locale, "voters_none");
+            format!("**{}**: {none}\n", sanitize(option))
+        } else {
+            let names = user_ids.iter().map(|user_id| name(*user_id));
+            format!(
+                "**{}**: {}\n",
+                sanitize(option),
+                names.collect::<Vec<_>>().join(", ")
+            )
+        };
+        if reply.chars().count() + line.chars().count() > MAX_CONTENT_LENGTH {
+            reply.push('…');
+            break;
+        }
+        reply.push_str(&line);
+    }
+    reply
+}
+
+async fn results(ctx: &Context, command: &ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let message_id = get_poll_message_id(command)?;
+
+    let id = POLLS
+        .find_by_message(message_id)
+        .await
+        .ok_or(PollError::PollNotFound)?;
+    let lock = POLLS.shard(id).read().await;
+    let poll_data = lock.get(&id).ok_or(PollError::PollNotFound)?;
+    if poll_data.results_hidden() {
+        anyhow::bail!(PollError::ResultsHidden);
+    }
+    let results = create_results(poll_data);
+    drop(lock);
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|response_data| {
+                    response_data
+                        .content(results)
+                        .allowed_mentions(|mentions| mentions.empty_parse())
+                })
+        })
+        .await
+        .context("failed to create response")?;
+    Ok(())
+}
+
+async fn list(ctx: &Context, command: &ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let mut lines = vec![];
+    for shard in POLLS.shards() {
+        for poll_data in shard.read().await.values() {
+            if poll_data.channel_id == command.channel_id
+                && !poll_data.closed
+                && !poll_data.scheduled
+            {
+                lines.push((
+                    poll_data.end_time,
+                    create_listing(poll_data, &command.locale),
+                ));
+            }
+        }
+    }
+    if lines.is_empty() {
+        let reply = i18n::text(&command.locale, "list_empty");
+        return respond_ephemeral(ctx, command, reply).await;
+    }
+    // the polls closing soonest are the most urgent
+    lines.sort_by_key(|(end_time, _)| *end_time);
+
+    let mut reply = i18n::format(&command.locale, "list", &[("count", &lines.len())]);
+    for (i, (_, line)) in lines.iter().enumerate() {
+        if reply.chars().count() + line.chars().count() + 1 + TRUNCATION_NOTICE_LENGTH
+            > MAX_CONTENT_LENGTH
+        {
+            reply.push('\n');
+            reply.push_str(&i18n::format(
+                &command.locale,
+                "and_more",
+                &[("count", &(lines.len() - i))],
+            ));
+            break;
+        }
+        reply.push('\n');
+        reply.push_str(line);
+    }
+    respond_ephemeral(ctx, command, &reply).await
+}
+
+/// Describes the poll in a line of the list of active polls
+fn create_listing(poll_data: &PollData, locale: &str) -> String {
+    let link = poll_data
+        .message_id
+        .link(poll_data.channel_id, poll_data.guild_id);
+    let name = match &poll_data.title {
+        Some(title) => format!("**{title}** ({link})"),
+        None => link,
+    };
+    let leaders = poll_data
+        .leaders()
+        .into_iter()
+        .map(sanitize)
+        .collect::<Vec<_>>();
+    let leading = match leaders.as_slice() {
+        _ if poll_data.results_hidden() => i18n::text(locale, "listing_hidden").to_string(),
+        [] => i18n::text(locale, "listing_no_votes").to_string(),
+        [leader] => i18n::format(locale, "listing_leader", &[("leader", leader)]),
+        leaders => i18n::format(locale, "listing_tie", &[("leaders", &leaders.join(", "))]),
+    };
+    let end = poll_data
+        .end_time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    i18n::format(
+        locale,
+        "listing",
+        &[("name", &name), ("leading", &leading), ("end", &end)],
+    )
+}
+
+async fn export(ctx: &Context, command: &ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let message_id = get_poll_message_id(command)?;
+    let voters = get_option(command, "voters")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let json = get_option(command, "format").and_then(|v| v.as_str()) == Some("json");
+
+    let id = POLLS
+        .find_by_message(message_id)
+        .await
+        .ok_or(PollError::PollNotFound)?;
+    let lock = POLLS.shard(id).read().await;
+    let poll_data = lock.get(&id).ok_or(PollError::PollNotFound)?;
+    if poll_data.results_hidden() {
+        anyhow::bail!(PollError::ResultsHidden);
+    }
+    let (file, extension) = if json {
+        (export::create_json(poll_data, voters)?, "json")
+    } else {
+        (export::create_csv(poll_data, voters), "csv")
+    };
+    drop(lock);
+
+    let filename = format!("poll-{message_id}.{extension}");
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|response_data| {
+                    response_data
+                        .add_file((file.as_bytes(), filename.as_str()))
+                        .ephemeral(true)
+                })
+        })
+        .await
+        .context("failed to create response")?;
+    Ok(())
+}
+
+async fn stats(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    owner_id: Option<UserId>,
+) -> anyhow::Result<()> {
+    if owner_id != Some(command.user.id) {
+        anyhow::bail!(PollError::NotOwner);
+    }
+    let mut active_polls = 0;
+    for shard in POLLS.shards() {
+        active_polls += shard.read().await.len();
+    }
+    let reply = i18n::format(
+        &command.locale,
+        "stats",
+        &[
+            ("created", &POLLS_CREATED.load(Ordering::Relaxed)),
+            ("votes", &VOTES_CAST.load(Ordering::Relaxed)),
+            ("active", &active_polls),
+        ],
+    );
+    respond_ephemeral(ctx, command, &reply).await
+}
+
+/// Registers the commands again, such as after changing their definitions. They're registered
+/// in the guild if the commands are registered in each guild and globally otherwise, like on
+/// startup, so that they don't show up twice
+async fn reload(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    owner_id: Option<UserId>,
+    guild_commands: bool,
+    definitions: &CreateApplicationCommands,
+) -> anyhow::Result<()> {
+    if owner_id != Some(command.user.id) {
+        anyhow::bail!(PollError::NotOwner);
+    }
+    let guild_id = if guild_commands {
+        let guild_id = command
+            .guild_id
+            .context("the reload command was used outside a guild")?;
+        Some(guild_id)
+    } else {
+        None
+    };
+    // registering the commands can take longer than Discord waits for a response
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|response_data| response_data.ephemeral(true))
+        })
+        .await
+        .context("failed to create response")?;
+    let res = match guild_id {
+        Some(guild_id) => {
+            guild_id
+                .set_application_commands(&ctx.http, |commands| {
+                    commands.0 = definitions.0.clone();
+                    commands
+                })
+                .await
+        }
+        None => {
+            Command::set_global_application_commands(&ctx.http, |commands| {
+                commands.0 = definitions.0.clone();
+                commands
+            })
+            .await
+        }
+    };
+    let reply = match res {
+        Ok(commands) => {
+            let key = match guild_id {
+                Some(_) => "reloaded",
+                None => "reloaded_global",
+            };
+            i18n::format(&command.locale, key, &[("count", &commands.len())])
+        }
+        Err(err) => {
+            tracing::error!("Failed to reload commands: {err}");
+            i18n::format(&command.locale, "reload_failed", &[("error", &err)])
+        }
+    };
+    command
+        .edit_original_interaction_response(&ctx.http, |response| response.content(reply))
+        .await
+        .context("failed to edit response")?;
+    Ok(())
+}
+
+/// Changes the guild's defaults for new polls and shows them
+async fn configure(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    config: &crate::config::Config,
+) -> anyhow::Result<()> {
+    let guild_id = command
+        .guild_id
+        .context("the config command was used outside a guild")?;
+    let mut settings = settings::get(Some(guild_id)).await;
+    if get_option(command, "reset").and_then(|v| v.as_bool()) == Some(true) {
+        settings = settings::GuildSettings::default();
+    }
+    if let Some(duration) = get_option(command, "duration").and_then(|v| v.as_str()) {
+        settings.default_duration = Some(parse_duration(duration)?);
+    }
+    if let Some(max_options) = get_option(command, "max_options").and_then(|v| v.as_u64()) {
+        settings.max_options = Some(max_options.try_into().unwrap_or(MAX_OPTIONS));
+    }
+    if let Some(anonymous) = get_option(command, "anonymous").and_then(|v| v.as_bool()) {
+        settings.anonymous = anonymous;
+    }
+    if let Some(colors) = get_option(command, "colors").and_then(|v| v.as_str()) {
+        settings.colors = match colors {
+            "secondary" => ButtonColors::Secondary,
+            "success" => ButtonColors::Success,
+            "danger" => ButtonColors::Danger,
+            "cycle" => ButtonColors::Cycle,
+            _ => ButtonColors::Primary,
+        };
+    }
+    // only saved if something was given, so that the command can also be used to see the settings
+    if !command.data.options.is_empty() {
+        settings::set(guild_id, settings, &config.settings_file).await?;
+    }
+
+    let effective = settings.apply(&config.poll);
+    let locale = &command.locale;
+    let bot_default = |overridden: bool| {
+        if overridden {
+            ""
+        } else {
+            i18n::text(locale, "settings_bot_default")
+        }
+    };
+    let colors = match settings.colors {
+        ButtonColors::Primary => "colors_primary",
+        ButtonColors::Secondary => "colors_secondary",
+        ButtonColors::Success => "colors_success",
+        ButtonColors::Danger => "colors_danger",
+        ButtonColors::Cycle => "colors_cycle",
+    };
+    let reply = i18n::format(
+        locale,
+        "settings",
+        &[
+            ("duration", &format_duration(effective.default_duration)),
+            (
+                "duration_default",
+                &bot_default(settings.default_duration.is_some()),
+            ),
+            ("max_options", &effective.max_options),
+            (
+                "max_options_default",
+                &bot_default(settings.max_options.is_some()),
+            ),
+            (
+                "anonymous",
+                &i18n::text(locale, if settings.anonymous { "yes" } else { "no" }),
+            ),
+            ("colors", &i18n::text(locale, colors)),
+        ],
+    );
+    respond_ephemeral(ctx, command, &reply).await
+}
+
+/// Closes every poll without announcing the results, for stopping floods of polls
+async fn purge(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    owner_id: Option<UserId>,
+) -> anyhow::Result<()> {
+    if owner_id != Some(command.user.id) {
+        anyhow::bail!(PollError::NotOwner);
+    }
+    let mut polls = vec![];
+    for shard in POLLS.shards() {
+        polls.extend(std::mem::take(&mut *shard.write().await).into_values());
+    }
+
+    // editing every poll message can take longer than Discord waits for a response
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|response_data| response_data.ephemeral(true))
+        })
+        .await
+        .context("failed to create response")?;
+    let mut closed = 0;
+    for mut poll_data in polls {
+        // polls closed early already have their buttons disabled
+        if poll_data.closed {
+            continue;
+        }
+        poll_data.closed = true;
+        closed += 1;
+        // scheduled polls haven't been posted yet
+        if poll_data.scheduled {
+            continue;
+        }
+        let res = poll_data
+            .channel_id
+            .edit_message(&ctx.http, poll_data.message_id, |message| {
+                message
+                    .content(create_ping(&poll_data))
+                    .set_embed(create_embed(&poll_data))
+                    .components(|c| c.set_action_rows(create_vote_components(&poll_data)))
+            })
+            .await;
+        if let Err(err) = res {
+            tracing::warn!("Failed to close poll {}: {err:#}", poll_data.message_id);
+        }
+        if poll_data.pinned {
+            if let Err(err) = poll_data
+                .channel_id
+                .unpin(&ctx.http, poll_data.message_id)
+                .await
+            {
+                tracing::warn!("Failed to unpin poll {}: {err:#}", poll_data.message_id);
+            }
+        }
+    }
+    let reply = if closed == 1 {
+        i18n::text(&command.locale, "purged_one").to_string()
+    } else {
+        i18n::format(&command.locale, "purged", &[("count", &closed)])
+    };
+    command
+        .edit_original_interaction_response(&ctx.http, |response| response.content(reply))
+        .await
+        .context("failed to edit response")?;
+    Ok(())
+}
+
+/// Creates a usage guide from the command definitions so that it stays up to date
+fn create_help(commands: &CreateApplicationCommands) -> Vec<String> {
+    let mut help = format!("**/{COMMAND}** options:");
+    let poll_options = commands
+        .0
+        .iter()
+        .find(|command| command["name"] == COMMAND)
+        .and_then(|command| command["options"].as_array())
+        .into_iter()
+        .flatten();
+    for option in poll_options {
+        let name = option["name"].as_str().unwrap_or_default();
+        let description = option["description"].as_str().unwrap_or_default();
+        let optional = if option["required"] == true {
+            ""
+        } else {
+            " (optional)"
+        };
+        help.push_str(&format!("\n`{name}`{optional}: {description}"));
+    }
+    help.push_str(&format!(
+        "\n\nSeparate options with commas or newlines, and wrap an option in double quotes \
+        or write `\\,` for a comma inside it, like `\"Rock, Paper, Scissors\"`. \
+        Options can be at most {MAX_OPTION_LENGTH} characters and can start with an emoji, \
+        like `🍕 Pizza`, which is shown on the option's button. \
+        Options like `Carpool[4]` can only get that many votes.\
+        \nDurations are a number followed by `s`, `m`, `h` or `d`, like `30m` or `2h`, \
+        or several of them from the largest unit to the smallest, like `1h30m`, \
+        and can be at most `{}`.\n",
+        format_duration(MAX_DURATION)
+    ));
+    for command in &commands.0 {
+        let name = command["name"].as_str().unwrap_or_default();
+        if [
+            COMMAND,
+            HELP_COMMAND,
+            STATS_COMMAND,
+            PURGE_COMMAND,
+            RELOAD_COMMAND,
+        ]
+        .contains(&name)
+        {
+            continue;
+        }
+        let description = command["description"].as_str().unwrap_or_default();
+        help.push_str(&format!("\n**/{name}**: {description}"));
+    }
+    split_lines(&help, MAX_CONTENT_LENGTH)
+}
+
+/// Splits the text into parts of at most the maximum length, between lines where possible
+fn split_lines(text: &str, max_length: usize) -> Vec<String> {
+    let mut parts = vec![];
+    let mut part = String::new();
+    for line in text.lines() {
+        // lines that don't fit in a part of their own are truncated
+        let line = truncate(line.to_string(), max_length);
+        let separator = usize::from(!part.is_empty());
+        if part.chars().count() + separator + line.chars().count() > max_length {
+            parts.push(std::mem::take(&mut part));
+        } else if separator == 1 {
+            part.push('\n');
+        }
+        part.push_str(&line);
+    }
+    if !part.is_empty() {
+        parts.push(part);
+    }
+    parts
+}
+
+/// Periodically removes old poll data from memory, announcing the results of expired polls.
+/// In a dry run the expired polls are only logged and kept as they are.
+pub async fn cleaner(http: Arc<Http>, config: Arc<crate::config::Config>) {
+    let reminder = config.reminder;
+    let dry_run = config.cleaner_dry_run;
+    let mut interval = tokio::time::interval(config.cleaner_interval);
+    loop {
+        interval.tick().await;
+        let mut expired = vec![];
+        let mut reminders = vec![];
+        let mut due = vec![];
+        for shard in POLLS.shards() {
+            let mut lock = shard.write().await;
+            let mut remove = vec![];
+            for (key, val) in lock.iter_mut() {
+                let now = SystemTime::now();
+                if val.scheduled && now < val.end_time {
+                    if now >= val.start_time {
+                        due.push(*key);
+                    }
+                } else if now >= val.end_time {
+                    if dry_run {
+                        let age = now.duration_since(val.start_time).unwrap_or_default();
+                        tracing::info!(
+                            "Would remove poll {} in channel {}, started {}s ago",
+                            key,
+                            val.channel_id,
+                            age.as_secs()
+                        );
+                    } else {
+                        remove.push(*key);
+                    }
+                } else if let Some(reminder) = reminder {
+                    let remaining = val.end_time.duration_since(now).unwrap_or_default();
+                    // polls shorter than the reminder window would be reminded of right away
+                    let length = val
+                        .end_time
+                        .duration_since(val.start_time)
+                        .unwrap_or_default();
+                    if !val.reminded && !val.closed && remaining <= reminder && length > reminder {
+                        val.reminded = true;
+                        reminders.push((
+                            val.channel_id,
+                            val.message_id,
+                            create_reminder(&val.locale, remaining),
+                            create_embed(val),
+                        ));
+                    }
+                }
+            }
+            for target in remove {
+                match lock.remove(&target) {
+                    // only happens if the bot was offline for the whole poll
+                    Some(poll_data) if poll_data.scheduled => {
+                        tracing::warn!("Scheduled poll {target} ended before it could be posted");
+                    }
+                    Some(poll_data) => expired.push((target, poll_data)),
+                    None => {}
+                }
+            }
+        }
+        COOLDOWNS
+            .lock()
+            .await
+            .retain(|_, until| *until > Instant::now());
+        LAST_VOTES
+            .lock()
+            .await
+            .retain(|_, last| last.elapsed() < VOTE_DEBOUNCE);
+        DRAFTS
+            .lock()
+            .await
+            .retain(|_, draft| draft.created.elapsed() < DRAFT_TIMEOUT);
+        for id in due {
+            if let Err(err) = post_scheduled(&http, id).await {
+                tracing::error!("Failed to post scheduled poll {id}: {err:#}");
+            }
+        }
+        // the polls are no longer in the map so the lock isn't needed to update the messages
+
+        for (id, mut poll_data) in expired {
+            log_outcome(id, &poll_data);
+            // polls closed early have already been announced
+            if poll_data.closed {
+                continue;
+            }
+            if let Err(err) = finish(&http, &mut poll_data).await {
+                tracing::error!("Failed to finish poll {}: {err:#}", poll_data.message_id);
+            }
+        }
+        for (channel_id, message_id, content, embed) in reminders {
+            // shows that the poll is closing soon
+            let res = channel_id
+                .edit_message(&http, message_id, |message| message.set_embed(embed))
+                .await;
+            if let Err(err) = res {
+                tracing::warn!("Failed to update poll {message_id}: {err:#}");
+            }
+            let res = channel_id
+                .send_message(&http, |message| {
+                    message
+                        .content(content)
+                        .reference_message((channel_id, message_id))
+                })
+                .await;
+            if let Err(err) = res {
+                tracing::error!("Failed to send reminder for poll {message_id}: {err:#}");
+            }
+        }
+    }
+}
+
+/// Creates the reminder of the time left in a poll, in minutes rounded up
+fn create_reminder(locale: &str, remaining: Duration) -> String {
+    let minutes = (remaining.as_secs() + 59) / 60;
+    if minutes == 1 {
+        i18n::text(locale, "reminder_one").to_string()
+    } else {
+        i18n::format(locale, "reminder", &[("minutes", &minutes)])
+    }
+}
+
+/// Logs the results of an expired poll as structured fields, for keeping a record of them
+fn log_outcome(id: InteractionId, poll_data: &PollData) {
+    let tallies = poll_data
+        .options
+        .iter()
+        .map(|option| (option.as_str(), poll_data.votes_for(option)))
+        .collect::<HashMap<_, _>>();
+    let tallies = serde_json::to_string(&tallies).unwrap_or_default();
+    let winners = serde_json::to_string(&poll_data.winners()).unwrap_or_default();
+    let duration = poll_data
+        .end_time
+        .duration_since(poll_data.start_time)
+        .unwrap_or_default();
+    tracing::info!(
+        poll_id = %id,
+        message_id = %poll_data.message_id,
+        channel_id = %poll_data.channel_id,
+        tallies = %tallies,
+        winners = %winners,
+        voters = poll_data.votes.len(),
+        abstentions = poll_data.abstentions(),
+        quorum_reached = poll_data.missing_votes() == 0,
+        closed_early = poll_data.closed,
+        duration_secs = duration.as_secs(),
+        "poll expired"
+    );
+}
+
+/// Closes the poll, showing the final results on the poll message and announcing the winner
+async fn finish(http: &Http, poll_data: &mut PollData) -> anyhow::Result<()> {
+    poll_data.closed = true;
+    // show the final results and disable voting
+    poll_data
+        .channel_id
+        .edit_message(http, poll_data.message_id, |message| {
+            message
+                .content(create_ping(poll_data))
+                .set_embed(create_embed(poll_data))
+                .components(|c| c.set_action_rows(create_vote_components(poll_data)))
+        })
+        .await
+        .context("failed to edit poll message")?;
+    wrap_up(http, poll_data).await
+}
+
+/// Unpins the closed poll and announces its results
+async fn wrap_up(http: &Http, poll_data: &mut PollData) -> anyhow::Result<()> {
+    if poll_data.pinned {
+        poll_data.pinned = false;
+        if let Err(err) = poll_data.channel_id.unpin(http, poll_data.message_id).await {
+            tracing::warn!("Failed to unpin poll {}: {err:#}", poll_data.message_id);
+        }
+    }
+
+    let announcement = create_announcement(poll_data);
+    if poll_data.thread_results {
+        match post_thread_results(http, poll_data, &announcement).await {
+            Ok(()) => return Ok(()),
+            // announced in the channel instead so that the results aren't lost
+            Err(err) => tracing::warn!(
+                "Failed to post the results of poll {} in a thread: {err:#}",
+                poll_data.message_id
+            ),
+        }
+    }
+    poll_data
+        .channel_id
+        .send_message(http, |message| {
+            message
+                .content(announcement)
+                .reference_message((poll_data.channel_id, poll_data.message_id))
+        })
+        .await
+        .context("failed to announce poll results")?;
+    Ok(())
+}
+
+/// Starts a thread off the closed poll with the announcement, the final results and who voted for
+/// what unless the poll is anonymous
+async fn post_thread_results(
+    http: &Http,
+    poll_data: &PollData,
+    announcement: &str,
+) -> anyhow::Result<()> {
+    let locale = &poll_data.locale;
+    let name = match &poll_data.title {
+        Some(title) => i18n::format(locale, "thread_name", &[("title", title)]),
+        None => i18n::text(locale, "poll").to_string(),
+    };
+    let thread = poll_data
+        .channel_id
+        .create_public_thread(http, poll_data.message_id, |thread| {
+            thread.name(truncate(name, MAX_THREAD_NAME_LENGTH))
+        })
+        .await
+        .context("failed to create thread")?;
+
+    let mut messages = vec![announcement.to_string(), create_results(poll_data)];
+    if !poll_data.anonymous {
+        // the mentions show the voters' names without notifying them
+        let voters = voters_by_option(poll_data, &poll_data.locale);
+        messages.push(create_voters(&voters, &poll_data.locale, |user_id| {
+            format!("<@{user_id}>")
+        }));
+    }
+    for content in messages {
+        thread
+            .id
+            .send_message(http, |message| {
+                message
+                    .content(content)
+                    .allowed_mentions(|mentions| mentions.empty_parse())
+            })
+            .await
+            .context("failed to post in thread")?;
+    }
+    Ok(())
+}
+
+/// Announces the winner of the closed poll, or the tie between several
+fn create_announcement(poll_data: &PollData) -> String {
+    let winners = poll_data
+        .winners()
+        .into_iter()
+        .map(sanitize)
+        .collect::<Vec<_>>();
+    let locale = &poll_data.locale;
+    let announcement = match winners.as_slice() {
+        _ if poll_data.missing_votes() > 0 => i18n::text(locale, "quorum_failed").to_string(),
+        [] => i18n::text(locale, "ended_no_votes").to_string(),
+        [winner] => i18n::format(locale, "ended_winner", &[("winner", winner)]),
+        winners if poll_data.ties == TiePolicy::Runoff => i18n::format(
+            locale,
+            "ended_tie_runoff",
+            &[("winners", &winners.join(", "))],
+        ),
+        winners => i18n::format(locale, "ended_tie", &[("winners", &winners.join(", "))]),
+    };
+    // a tie between many long options can be too long for a message
+    truncate(announcement, MAX_CONTENT_LENGTH)
+}
+
+/// The error code in Discord's response, if the error was caused by an unsuccessful request
+fn discord_error_code(err: &anyhow::Error) -> Option<isize> {
+    err.chain().find_map(|err| match err.downcast_ref()? {
+        SerenityError::Http(err) => match err.as_ref() {
+            HttpError::UnsuccessfulRequest(response) => Some(response.error.code),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Whether the interaction can't be responded to anymore, usually because it wasn't responded to
+/// within Discord's 3 second deadline
+fn is_stale_interaction(err: &anyhow::Error) -> bool {
+    matches!(
+        discord_error_code(err),
+        Some(UNKNOWN_INTERACTION | ALREADY_ACKNOWLEDGED)
+    )
+}
+
+/// Returns the value of an optional command option
+fn get_option<'a>(command: &'a ApplicationCommandInteraction, name: &str) -> Option<&'a Value> {
+    command
+        .data
+        .options
+        .iter()
+        .find(|o| o.name == name)
+        .and_then(|o| o.value.as_ref())
+}
+
+/// Responds to the command with a message only visible to the user
+pub async fn respond_ephemeral(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    content: &str,
+) -> anyhow::Result<()> {
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|response_data| {
+                    response_data
+                        .content(content)
+                        .ephemeral(true)
+                        .allowed_mentions(|mentions| mentions.empty_parse())
+                })
+        })
+        .await
+        .context("failed to create response")?;
+    Ok(())
+}
+
+/// Responds to the command with messages only visible to the user, the first part as the
+/// response and the rest as followups
+pub async fn respond_ephemeral_parts(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    parts: &[String],
+) -> anyhow::Result<()> {
+    let Some((first, rest)) = parts.split_first() else {
+        return Ok(());
+    };
+    respond_ephemeral(ctx, command, first).await?;
+    for part in rest {
+        command
+            .create_followup_message(&ctx.http, |followup| {
+                followup
+                    .content(part)
+                    .ephemeral(true)
+                    .allowed_mentions(|mentions| mentions.empty_parse())
+            })
+            .await
+            .context("failed to create followup message")?;
+    }
+    Ok(())
+}
+
+/// Responds to the component interaction with a message only visible to the user
+pub async fn respond_component_ephemeral(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    content: &str,
+) -> anyhow::Result<()> {
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|response_data| {
+                    response_data.content(content).ephemeral(true)
+                })
+        })
+        .await
+        .context("failed to create response")?;
+    Ok(())
+}
+
+/// Responds to the modal submission with a message only visible to the user
+pub async fn respond_modal_ephemeral(
+    ctx: &Context,
+    interaction: &ModalSubmitInteraction,
+    content: &str,
+) -> anyhow::Result<()> {
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|response_data| {
+                    response_data.content(content).ephemeral(true)
+                })
+        })
+        .await
+        .context("failed to create response")?;
+    Ok(())
+}
+
+/// Periodically updates the messages of the polls that were voted in since the last update, so
+/// that votes in quick succession only cause one edit
+pub async fn flusher(http: Arc<Http>, interval: Duration) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        let outdated = std::mem::take(&mut *OUTDATED.lock().await);
+        for id in outdated {
+            // the message is built with the lock held, but edited without it so that voting isn't
+            // blocked while waiting for Discord
+            let update = POLLS.shard(id).read().await.get(&id).map(|poll_data| {
+                (
+                    poll_data.channel_id,
+                    poll_data.message_id,
+                    create_ping(poll_data),
+                    create_embed(poll_data),
+                    create_vote_components(poll_data),
+                )
+            });
+            let Some((channel_id, message_id, content, embed, components)) = update else {
+                continue;
+            };
+            let res = channel_id
+                .edit_message(&http, message_id, |message| {
+                    message
+                        .content(content)
+                        .set_embed(embed)
+                        .components(|c| c.set_action_rows(components))
+                })
+                .await;
+            if let Err(err) = res {
+                tracing::warn!("Failed to update poll {message_id}: {err:#}");
+            }
+        }
+    }
+}
+
+/// Writes all polls to the given file
+pub async fn save(path: &Path) -> anyhow::Result<()> {
+    let json = {
+        let mut locks = vec![];
+        for shard in POLLS.shards() {
+            locks.push(shard.read().await);
+        }
+        let polls = locks
+            .iter()
+            .flat_map(|lock| lock.iter())
+            .collect::<HashMap<_, _>>();
+        serde_json::to_vec(&polls).context("failed to serialize polls")?
+    };
+    write_file(path, json).await
+}
+
+/// Replaces the file's contents by writing them to a temporary file first, so that a crash
+/// mid-write doesn't corrupt the file
+async fn write_file(path: &Path, contents: Vec<u8>) -> anyhow::Result<()> {
+    // named after the whole file name, so that files that only differ by extension don't share it
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    tokio::fs::write(&temp, contents)
+        .await
+        .with_context(|| format!("failed to write {}", temp.display()))?;
+    tokio::fs::rename(&temp, path)
+        .await
+        .with_context(|| format!("failed to rename {} to {}", temp.display(), path.display()))?;
+    Ok(())
+}
+
+/// Restores polls saved with `save`, doing nothing if the file doesn't exist
+pub async fn load(path: &Path) -> anyhow::Result<()> {
+    let json = match tokio::fs::read(path).await {
+        Ok(json) => json,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let polls: HashMap<InteractionId, PollData> =
+        serde_json::from_slice(&json).context("failed to deserialize polls")?;
+    tracing::info!("restored {} polls from {}", polls.len(), path.display());
+    for (id, mut poll_data) in polls {
+        poll_data.recount();
+        POLLS.shard(id).write().await.insert(id, poll_data);
+    }
+    Ok(())
+}
+
+/// Periodically saves polls to the given file
+pub async fn saver(path: PathBuf, interval: Duration) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        if let Err(err) = save(&path).await {
+            tracing::error!("Failed to save polls: {err:#}");
+        }
+    }
+}
+
+/// The options of a new poll, parsed from the command's options option
+struct ParsedOptions {
+    // sorted by their text, or in the order they were entered
+    options: Vec<String>,
+    // the capacities of options like `Carpool[4]`, by the options' IDs
+    caps: HashMap<String, u32>,
+    // how many duplicate options were removed
+    duplicates: usize,
+}
+
+/// Parses and validates the options, removing duplicates unless strict
+fn parse_options(
+    input: &str,
+    max_options: usize,
+    strict: bool,
+    case_sensitive: bool,
+    sort: SortOrder,
+) -> Result<ParsedOptions, PollError> {
+    // options like `Carpool[4]` can only get that many votes
+    let mut caps = HashMap::new();
+    let mut options = split_options(input)
+        .into_iter()
+        .map(|option| {
+            let option = option.trim();
+            match split_cap(option) {
+                Some((text, cap)) => {
+                    caps.insert(option_id(text).to_string(), cap);
+                    text.to_string()
+                }
+                None => option.to_string(),
+            }
+        })
+        .filter(|option| !option.is_empty())
+        .collect::<Vec<_>>();
+    if options.is_empty() {
+        return Err(PollError::NoOptions);
+    }
+    // options of only zero-width spaces and the like would make blank buttons
+    if options.iter().any(|option| !is_visible(option_id(option))) {
+        return Err(PollError::InvisibleOption);
+    }
+    // the first of several duplicates is the one that's kept
+    let total_options = options.len();
+    let mut seen = HashSet::new();
+    options.retain(|option| seen.insert(normalize_option(option, case_sensitive)));
+    let duplicates = total_options - options.len();
+    if sort == SortOrder::Alpha {
+        options.sort_by_cached_key(|option| normalize_option(option, case_sensitive));
+    }
+    if strict && duplicates > 0 {
+        return Err(PollError::DuplicateOptions(duplicates));
+    }
+    if options.len() > max_options {
+        return Err(PollError::TooManyOptions(options.len(), max_options));
+    }
+    // the capacity is shown after the votes in the button labels
+    if let Some(option) = options.iter().find(|option| {
+        let cap_length = caps
+            .get(option_id(option))
+            .map_or(0, |cap| cap.to_string().len() + 1);
+        option_id(option).chars().count() + cap_length > MAX_OPTION_LENGTH
+    }) {
+        return Err(PollError::OptionTooLong(option.clone()));
+    }
+    Ok(ParsedOptions {
+        options,
+        caps,
+        duplicates,
+    })
+}
+
+/// The form of the option that's compared to find duplicates: its text without the emoji, with
+/// runs of whitespace collapsed and, unless case sensitive, lowercased so that `Yes` and `yes `
+/// are the same option
+fn normalize_option(option: &str, case_sensitive: bool) -> String {
+    let normalized = option_id(option.trim())
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if case_sensitive {
+        normalized
+    } else {
+        normalized.to_lowercase()
+    }
+}
+
+/// Whether the text has at least one character that isn't whitespace or invisible
+fn is_visible(text: &str) -> bool {
+    text.chars().any(|c| {
+        !c.is_whitespace()
+            && !matches!(
+                c,
+                // soft hyphen, combining grapheme joiner and Mongolian vowel separator
+                '\u{AD}' | '\u{34F}' | '\u{180E}'
+                // zero-width spaces and joiners, direction marks, word joiner and invisible operators
+                | '\u{200B}'..='\u{200F}'
+                | '\u{2060}'..='\u{2064}'
+                // direction embeddings, overrides and isolates
+                | '\u{202A}'..='\u{202E}'
+                | '\u{2066}'..='\u{206F}'
+                // Hangul fillers, which render as blank space
+                | '\u{115F}' | '\u{1160}' | '\u{3164}' | '\u{FFA0}'
+                // variation selectors, byte order mark and tags
+                | '\u{FE00}'..='\u{FE0F}'
+                | '\u{FEFF}'
+                | '\u{E0000}'..='\u{E007F}'
+                | '\u{E0100}'..='\u{E01EF}'
+            )
+    })
+}
+
+/// Splits the options on commas or newlines, treating `\,` as a literal comma and keeping the
+/// commas in options wrapped in double quotes, like `"Rock, Paper, Scissors"`
+fn split_options(options: &str) -> Vec<String> {
+    let mut split = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = options.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if matches!(chars.peek(), Some(',' | '\\' | '"')) => {
+                current.extend(chars.next());
+            }
+            // phones may replace the quotes with curly ones as they're typed
+            '"' | '”' if quoted => quoted = false,
+            // only quotes at the start of an option are special, so that options like
+            // `12" pizza` are kept as they are
+            '"' | '“' if current.trim().is_empty() => {
+                current.clear();
+                quoted = true;
+            }
+            ',' | '\n' if !quoted => split.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    split.push(current);
+    split
+}
+
+/// Splits a capacity like in `Carpool[4]` from the option's text
+fn split_cap(option: &str) -> Option<(&str, u32)> {
+    let (text, cap) = option.strip_suffix(']')?.rsplit_once('[')?;
+    let text = text.trim_end();
+    let cap = cap.parse().ok().filter(|cap| *cap > 0)?;
+    (!text.is_empty()).then_some((text, cap))
+}
+
+/// Parses role weights like `@Mod=2, @Admin=3`, where the roles are mentions or IDs
+fn parse_weights(weights: &str) -> Result<HashMap<RoleId, u32>, PollError> {
+    let invalid = || PollError::InvalidWeights(weights.to_string());
+    weights
+        .split(',')
+        .map(str::trim)
+        .filter(|weight| !weight.is_empty())
+        .map(|weight| {
+            let (role, weight) = weight.split_once('=').ok_or_else(invalid)?;
+            let role = role.trim();
+            // mentioning a role in a string option inserts it as <@&id>
+            let role = role
+                .strip_prefix("<@&")
+                .and_then(|role| role.strip_suffix('>'))
+                .unwrap_or(role);
+            let weight = weight.trim();
+            // all digits but too large to parse is still a weight, just too high of one
+            let weight = match weight.parse::<u32>() {
+                Ok(weight) => weight,
+                Err(_) if !weight.is_empty() && weight.bytes().all(|b| b.is_ascii_digit()) => {
+                    return Err(PollError::WeightTooHigh);
+                }
+                Err(_) => return Err(invalid()),
+            };
+            if weight == 0 {
+                return Err(invalid());
+            }
+            if weight > MAX_WEIGHT {
+                return Err(PollError::WeightTooHigh);
+            }
+            Ok((RoleId(role.parse().map_err(|_| invalid())?), weight))
+        })
+        .collect()
+}
+
+/// Parses durations like `30s`, `30m`, `2h`, `1d` or combinations of them from the largest unit
+/// to the smallest like `1h30m`, which can be at most `MAX_DURATION`
+fn parse_duration(duration: &str) -> Result<Duration, PollError> {
+    let invalid = || PollError::InvalidDuration(duration.to_string());
+    let mut rest = duration.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    let mut total: u64 = 0;
+    // each unit can only be used once, after the larger ones
+    let mut previous_unit = u64::MAX;
+    while !rest.is_empty() {
+        // an amount without a unit isn't accepted so that `90` isn't mistaken for seconds
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .filter(|digits| *digits > 0)
+            .ok_or_else(invalid)?;
+        // the amount is all digits, so it only fails to parse if it doesn't fit
+        let amount: u64 = rest[..digits]
+            .parse()
+            .map_err(|_| PollError::DurationTooLong)?;
+        let unit = rest[digits..].chars().next().ok_or_else(invalid)?;
+        let seconds = match unit {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 60 * 60 * 24,
+            _ => return Err(invalid()),
+        };
+        if seconds >= previous_unit {
+            return Err(invalid());
+        }
+        previous_unit = seconds;
+        total = amount
+            .checked_mul(seconds)
+            .and_then(|seconds| total.checked_add(seconds))
+            .ok_or(PollError::DurationTooLong)?;
+        rest = rest[digits + unit.len_utf8()..].trim_start();
+    }
+    let duration = Duration::from_secs(total);
+    if duration.is_zero() {
+        Err(invalid())
+    } else if duration > MAX_DURATION {
+        Err(PollError::DurationTooLong)
+    } else {
+        Ok(duration)
+    }
+}
+
+/// Formats the duration in the largest unit it's a whole number of, like `90m`, so that it can
+/// be parsed back with `parse_duration`
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    for (unit, length) in [('d', 60 * 60 * 24), ('h', 60 * 60), ('m', 60)] {
+        if seconds >= length && seconds % length == 0 {
+            return format!("{}{unit}", seconds / length);
+        }
+    }
+    format!("{seconds}s")
+}
+
+/// Parses times like `2024-06-01T18:00Z`, `2024-06-01 18:00:30+02:00` or `2024-06-01T18:00`,
+/// which is treated as UTC
+fn parse_end_time(ends: &str) -> Option<SystemTime> {
+    let ends = ends.trim();
+    let (date, rest) = ends.split_once(['T', 't', ' '])?;
+    // the offset is subtracted from the local time to get the time in UTC
+    let (time, offset) = if let Some(time) = rest.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else if let Some(i) = rest.rfind(['+', '-']) {
+        let sign = if rest[i..].starts_with('+') { 1 } else { -1 };
+        let (hours, minutes) = rest[i + 1..].split_once(':')?;
+        let hours: i64 = hours.parse().ok().filter(|h| *h < 24)?;
+        let minutes: i64 = minutes.parse().ok().filter(|m| *m < 60)?;
+        (&rest[..i], sign * (hours * 60 + minutes) * 60)
+    } else {
+        (rest, 0)
+    };
+
+    let mut date = date.splitn(3, '-');
+    let year: i64 = date.next()?.parse().ok()?;
+    let month: u32 = date.next()?.parse().ok()?;
+    let day: u32 = date.next()?.parse().ok()?;
+    let mut time = time.splitn(3, ':');
+    let hour: i64 = time.next()?.parse().ok().filter(|h| *h < 24)?;
+    let minute: i64 = time.next()?.parse().ok().filter(|m| *m < 60)?;
+    let second: i64 = match time.next() {
+        Some(second) => second.parse().ok().filter(|s| *s < 60)?,
+        None => 0,
+    };
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let secs =
+        days_from_civil(year, month, day) * 60 * 60 * 24 + hour * 60 * 60 + minute * 60 + second
+            - offset;
+    let secs = u64::try_from(secs).ok()?;
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The number of days from the unix epoch to the date, from
+/// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((i64::from(month) + 9) % 12) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Reads the message ID from the command's `poll` option
+fn get_poll_message_id(command: &ApplicationCommandInteraction) -> anyhow::Result<MessageId> {
+    let poll = get_option(command, "poll")
+        .context("missing poll")?
+        .as_str()
+        .context("invalid poll value")?;
+    let message_id = parse_message_id(poll).ok_or(PollError::PollNotFound)?;
+    Ok(message_id)
+}
+
+/// Accepts either a message link or a plain message ID
+fn parse_message_id(poll: &str) -> Option<MessageId> {
+    let id = poll.trim().rsplit('/').next()?;
+    id.parse().ok().map(MessageId)
+}
+
+/// The content of the poll message, which mentions the role to notify of the poll, if any
+fn create_ping(poll_data: &PollData) -> String {
+    poll_data
+        .ping
+        .map(|role| format!("<@&{role}>"))
+        .unwrap_or_default()
+}
+
+/// Creates the poll message's embed, with the results and the end time
+fn create_embed(poll_data: &PollData) -> CreateEmbed {
+    let locale = &poll_data.locale;
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(
+            poll_data
+                .title
+                .as_deref()
+                .unwrap_or_else(|| i18n::text(locale, "poll")),
+        )
+        .description(create_description(poll_data))
+        .colour(if poll_data.closed {
+            CLOSED_COLOUR
+        } else if poll_data.reminded {
+            // the reminder is sent when the poll enters the reminder window
+            CLOSING_COLOUR
+        } else {
+            OPEN_COLOUR
+        })
+        .footer(|footer| {
+            footer.text(i18n::text(
+                locale,
+                if poll_data.closed { "ended" } else { "ends" },
+            ))
+        });
+    // shown in each user's own timezone
+    let end_secs = poll_data
+        .end_time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    if let Ok(end_time) = Timestamp::from_unix_timestamp(end_secs as i64) {
+        embed.timestamp(end_time);
+    }
+    if let Some(image) = &poll_data.image {
+        embed.image(image);
+    }
+    embed
+}
+
+fn create_description(poll_data: &PollData) -> String {
+    let locale = &poll_data.locale;
+    let header = if poll_data.closed {
+        i18n::text(locale, "final_results").to_string()
+    } else {
+        i18n::text(locale, "vote").to_string()
+    };
+    // mentions in embeds never notify the mentioned creator
+    let header = if poll_data.closed {
+        let started = poll_data
+            .start_time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let attribution = i18n::format(
+            locale,
+            "attribution",
+            &[("creator", &poll_data.creator), ("started", &started)],
+        );
+        format!("{header}\n{attribution}")
+    } else {
+        header
+    };
+    let header = if poll_data.kind == PollKind::Ranked && !poll_data.closed {
+        format!("{header}\n{}", i18n::text(locale, "ranked_hint"))
+    } else {
+        header
+    };
+    let header = match create_quorum_status(poll_data) {
+        Some(status) => format!("{header}\n{status}"),
+        None => header,
+    };
+    let header = match create_outcome(poll_data) {
+        Some(outcome) => format!("{header}\n{outcome}"),
+        None => header,
+    };
+    let content = create_tallies(poll_data, header, MAX_DESCRIPTION_LENGTH);
+    let content = if poll_data.kind == PollKind::Ranked && poll_data.closed {
+        append_rounds(poll_data, content)
+    } else {
+        content
+    };
+    // the tallies are truncated to fit, but a long title or tie can still take up too much room
+    truncate(content, MAX_DESCRIPTION_LENGTH)
+}
+
+/// Appends the rounds of the instant-runoff, as far as they fit in the embed
+fn append_rounds(poll_data: &PollData, mut content: String) -> String {
+    let mut length = content.chars().count();
+    for (i, round) in poll_data.runoff().rounds.iter().enumerate() {
+        let tallies = round
+            .tallies
+            .iter()
+            .map(|(option, votes)| format!("{} {votes}", sanitize(option)))
+            .collect::<Vec<_>>();
+        let mut line = format!(
+            "\n{}",
+            i18n::format(
+                &poll_data.locale,
+                "round",
+                &[("round", &(i + 1)), ("tallies", &tallies.join(", "))]
+            )
+        );
+        if !round.eliminated.is_empty() {
+            line.push_str(&i18n::format(
+                &poll_data.locale,
+                "eliminated",
+                &[(
+                    "options",
+                    &round
+                        .eliminated
+                        .iter()
+                        .map(|option| sanitize(option))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )],
+            ));
+        }
+        let line_length = line.chars().count();
+        if length + line_length > MAX_DESCRIPTION_LENGTH {
+            break;
+        }
+        content.push_str(&line);
+        length += line_length;
+    }
+    content
+}
+
+/// Describes whether a poll with a quorum has reached it, and its winners if so
+fn create_quorum_status(poll_data: &PollData) -> Option<String> {
+    poll_data.quorum?;
+    let locale = &poll_data.locale;
+    let missing = poll_data.missing_votes();
+    let status = if missing > 0 {
+        if poll_data.closed {
+            i18n::text(locale, "quorum_failed").to_string()
+        } else if missing == 1 {
+            i18n::text(locale, "quorum_missing_one").to_string()
+        } else {
+            i18n::format(locale, "quorum_missing", &[("missing", &missing)])
+        }
+    } else if poll_data.closed || poll_data.results_hidden() {
+        // the outcome is shown on its own line once the poll closes, and not at all before then
+        // if the results are hidden
+        i18n::text(locale, "quorum_reached").to_string()
+    } else {
+        let winners = poll_data
+            .winners()
+            .into_iter()
+            .map(sanitize)
+            .collect::<Vec<_>>();
+        match winners.as_slice() {
+            [] => i18n::text(locale, "quorum_reached").to_string(),
+            [winner] => i18n::format(locale, "quorum_leading", &[("winner", winner)]),
+            winners => i18n::format(locale, "quorum_tied", &[("winners", &winners.join(", "))]),
+        }
+    };
+    Some(status)
+}
+
+/// Describes the winner of a closed poll, or the tie between several
+fn create_outcome(poll_data: &PollData) -> Option<String> {
+    if !poll_data.closed || poll_data.missing_votes() > 0 {
+        return None;
+    }
+    let locale = &poll_data.locale;
+    let winners = poll_data
+        .winners()
+        .into_iter()
+        .map(sanitize)
+        .collect::<Vec<_>>();
+    let outcome = match winners.as_slice() {
+        [] => return None,
+        [winner] => i18n::format(locale, "winner", &[("winner", winner)]),
+        winners => {
+            let key = if poll_data.ties == TiePolicy::Runoff {
+                "tie_runoff"
+            } else {
+                "tie"
+            };
+            i18n::format(locale, key, &[("winners", &winners.join(", "))])
+        }
+    };
+    Some(outcome)
+}
+
+/// Creates a summary of the poll's current results that links back to the poll
+fn create_results(poll_data: &PollData) -> String {
+    let link = poll_data
+        .message_id
+        .link(poll_data.channel_id, poll_data.guild_id);
+    let locale = &poll_data.locale;
+    let header = match &poll_data.title {
+        Some(title) => i18n::format(
+            locale,
+            "results_title",
+            &[("title", title), ("link", &link)],
+        ),
+        None => i18n::format(locale, "results", &[("link", &link)]),
+    };
+    truncate(
+        create_tallies(poll_data, header, MAX_CONTENT_LENGTH),
+        MAX_CONTENT_LENGTH,
+    )
+}
+
+/// Appends a line with the votes for each option to the header, truncating to the maximum length
+/// if necessary, followed by the abstentions and totals
+fn create_tallies(poll_data: &PollData, mut content: String, max_length: usize) -> String {
+    let tallies = poll_data
+        .sorted_options()
+        .into_iter()
+        .map(|option| (option, poll_data.votes_for(option)))
+        .collect::<Vec<_>>();
+    // abstentions are left out so that the percentages only compare the options
+    let total = tallies.iter().map(|(_, votes)| votes).sum::<u32>();
+    let locale = &poll_data.locale;
+    let mut footer = String::new();
+    if poll_data.results_hidden() {
+        footer.push('\n');
+        footer.push_str(i18n::text(locale, "results_hidden"));
+    } else if poll_data.abstain {
+        footer.push('\n');
+        footer.push_str(&i18n::format(
+            locale,
+            "abstained",
+            &[("count", &poll_data.abstentions())],
+        ));
+    }
+    // the totals differ for multi-select polls, where each voter can vote for several options
+    let voters = poll_data.votes.len();
+    let key = if voters == 1 {
+        "total_votes_one"
+    } else {
+        "total_votes"
+    };
+    if !poll_data.results_hidden() {
+        footer.push('\n');
+        footer.push_str(&i18n::format(
+            locale,
+            key,
+            &[("total", &total), ("voters", &voters)],
+        ));
+    }
+    if let Some(eligible) = poll_data.eligible.filter(|_| !poll_data.results_hidden()) {
+        footer.push('\n');
+        footer.push_str(&i18n::format(
+            locale,
+            "turnout",
+            &[("voters", &voters), ("eligible", &eligible)],
+        ));
+    }
+
+    // the leading options are bold so that it's clear which is winning, all of them if tied
+    let leaders = poll_data.leaders();
+    let mut length = content.chars().count() + footer.chars().count();
+    for (i, (option, votes)) in tallies.iter().enumerate() {
+        // weighted polls also show how many people the votes came from
+        let formatted =
+            if poll_data.is_weighted() && !poll_data.caps.contains_key(option_id(option)) {
+                let voters = poll_data.voters_for(option);
+                let key = if voters == 1 {
+                    "weighted_votes_one"
+                } else {
+                    "weighted_votes"
+                };
+                i18n::format(locale, key, &[("votes", votes), ("count", &voters)])
+            } else {
+                poll_data.format_votes(option)
+            };
+        let bar = create_bar(*votes, total);
+        let name = sanitize(option);
+        let line = if poll_data.results_hidden() {
+            format!("\n{name}")
+        } else if leaders.contains(&option.as_str()) {
+            format!("\n{bar} **{name}**: {formatted}")
+        } else {
+            format!("\n{bar} {name}: {formatted}")
+        };
+        let line_length = line.chars().count();
+        // leave room for the truncation notice in case the following options don't fit
+        let remaining = tallies.len() - i - 1;
+        let reserved = if remaining > 0 {
+            TRUNCATION_NOTICE_LENGTH
+        } else {
+            0
+        };
+        if length + line_length + reserved > max_length {
+            content.push('\n');
+            content.push_str(&i18n::format(
+                locale,
+                "and_more",
+                &[("count", &(tallies.len() - i))],
+            ));
+            break;
+        }
+        content.push_str(&line);
+        length += line_length;
+    }
+    content.push_str(&footer);
+    content
+}
+
+// Discord's limits for message content and embed descriptions
+const MAX_CONTENT_LENGTH: usize = 2000;
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+// Discord's green for open polls, yellow for polls closing soon and grey for closed ones
+const OPEN_COLOUR: u32 = 0x57F287;
+const CLOSING_COLOUR: u32 = 0xFEE75C;
+const CLOSED_COLOUR: u32 = 0x979C9F;
+// enough for "\n…and N more" with any realistic number of options
+const TRUNCATION_NOTICE_LENGTH: usize = 20;
+// appended to text cut off by truncate
+const TRUNCATED: &str = "…(truncated)";
+const BAR_LENGTH: u32 = 8;
+
+/// Cuts the text off at the maximum length, marking where it was cut, as a last resort for
+/// text that would otherwise be rejected by Discord
+fn truncate(text: String, max_length: usize) -> String {
+    if text.chars().count() <= max_length {
+        return text;
+    }
+    let mut truncated = text
+        .chars()
+        .take(max_length - TRUNCATED.chars().count())
+        .collect::<String>();
+    truncated.push_str(TRUNCATED);
+    truncated
+}
+
+/// Creates a text progress bar followed by the percentage, e.g. `████░░░░ 50%`
+fn create_bar(votes: u32, total: u32) -> String {
+    // in u64 so that many heavily weighted votes can't overflow
+    let (votes, total) = (u64::from(votes), u64::from(total));
+    let filled = (votes * u64::from(BAR_LENGTH) + total / 2)
+        .checked_div(total)
+        .unwrap_or(0);
+    let percentage = (votes * 100 + total / 2).checked_div(total).unwrap_or(0);
+    let mut bar = String::new();
+    for i in 0..BAR_LENGTH {
+        bar.push(if u64::from(i) < filled { '█' } else { '░' });
+    }
+    format!("{bar} {percentage}%")
+}
+
+/// Creates a select menu for polls with many options, or voting buttons split into action rows
+/// of at most five buttons each. Always built from the poll data rather than the message's
+/// current components so that stale counts can't carry over
+fn create_vote_components(poll_data: &PollData) -> Vec<CreateActionRow> {
+    if uses_select_menu(poll_data) {
+        let mut row = CreateActionRow::default();
+        row.add_select_menu(create_vote_select_menu(poll_data));
+        let mut buttons = vec![create_my_vote_button(&poll_data.locale)];
+        if !poll_data.anonymous && !poll_data.results_hidden() {
+            buttons.push(create_voters_button(&poll_data.locale));
+        }
+        if poll_data.open_additions {
+            buttons.push(create_add_option_button(&poll_data.locale));
+        }
+        let mut buttons_row = CreateActionRow::default();
+        for mut button in buttons {
+            button.disabled(poll_data.closed);
+            buttons_row.add_button(button);
+        }
+        return vec![row, buttons_row];
+    }
+    let mut buttons = poll_data
+        .sorted_options()
+        .into_iter()
+        .map(|option| create_vote_button(poll_data, option))
+        .collect::<Vec<_>>();
+    if poll_data.abstain {
+        buttons.push(create_abstain_button(poll_data));
+    }
+    // polls with the maximum number of options don't have room for the other buttons
+    if buttons.len() < MAX_OPTIONS {
+        buttons.push(create_my_vote_button(&poll_data.locale));
+    }
+    if !poll_data.anonymous && !poll_data.results_hidden() && buttons.len() < MAX_OPTIONS {
+        buttons.push(create_voters_button(&poll_data.locale));
+    }
+    if poll_data.open_additions && buttons.len() < MAX_OPTIONS {
+        buttons.push(create_add_option_button(&poll_data.locale));
+    }
+    // Discord rejects messages with more than five rows, which would leave the poll without any
+    // buttons. New polls can't have that many options, but polls restored from a message or an
+    // older save might, so the buttons that don't fit are left out instead
+    if buttons.len() > MAX_OPTIONS {
+        tracing::warn!(
+            "Poll {} has {} buttons, leaving out the last {}",
+            poll_data.message_id,
+            buttons.len(),
+            buttons.len() - MAX_OPTIONS
+        );
+        buttons.truncate(MAX_OPTIONS);
+    }
+    buttons
+        .chunks_mut(BUTTONS_PER_ROW)
+        .map(|buttons| {
+            let mut row = CreateActionRow::default();
+            for button in buttons {
+                if poll_data.closed {
+                    button.disabled(true);
+                }
+                row.add_button(button.clone());
+            }
+            row
+        })
+        .collect()
+}
+
+/// Buttons get crowded with many options, but ranked polls need them to be clicked in order
+fn uses_select_menu(poll_data: &PollData) -> bool {
+    poll_data.options.len() > BUTTONS_PER_ROW && poll_data.kind != PollKind::Ranked
+}
+
+fn create_vote_select_menu(poll_data: &PollData) -> CreateSelectMenu {
+    let mut menu = CreateSelectMenu::default();
+    menu.custom_id(SELECT_MENU_ID)
+        .disabled(poll_data.closed)
+        // zero values lets users retract their vote
+        .min_values(0);
+    match poll_data.kind {
+        PollKind::Multi => menu
+            .placeholder(i18n::text(&poll_data.locale, "choose_options"))
+            .max_values((poll_data.options.len() + usize::from(poll_data.abstain)) as u64),
+        PollKind::Single | PollKind::Ranked => menu
+            .placeholder(i18n::text(&poll_data.locale, "choose_option"))
+            .max_values(1),
+    };
+    menu.options(|options| {
+        for option in poll_data.sorted_options() {
+            let (emoji, text) = split_emoji(option);
+            let mut menu_option = CreateSelectMenuOption::new(
+                create_label(poll_data, text, option),
+                poll_data.position(option).to_string(),
+            );
+            if let Some(emoji) = emoji {
+                menu_option.emoji(emoji);
+            }
+            options.add_option(menu_option);
+        }
+        if poll_data.abstain {
+            options.create_option(|option| {
+                option
+                    .label(create_label(
+                        poll_data,
+                        i18n::text(&poll_data.locale, "button_abstain"),
+                        ABSTAIN_ID,
+                    ))
+                    .value(ABSTAIN_ID)
+            });
+        }
+        options
+    });
+    menu
+}
+
+fn create_vote_button(poll_data: &PollData, option: &str) -> CreateButton {
+    let (emoji, text) = split_emoji(option);
+    let position = poll_data.position(option);
+    let mut button = CreateButton::default();
+    button
+        .custom_id(format!("{OPTION_PREFIX}{position}"))
+        .label(create_label(poll_data, text, option))
+        // the colors follow the options' positions, so sorting by votes doesn't change them
+        .style(poll_data.colors.style(position))
+        .disabled(poll_data.is_full(option));
+    if let Some(emoji) = emoji {
+        button.emoji(emoji);
+    }
+    button
+}
+
+/// Creates the buttons for posting or cancelling the preview of an important poll
+fn create_draft_buttons(locale: &str) -> CreateActionRow {
+    let mut row = CreateActionRow::default();
+    row.create_button(|button| {
+        button
+            .custom_id(POST_DRAFT_ID)
+            .label(i18n::text(locale, "button_post"))
+            .style(ButtonStyle::Success)
+    })
+    .create_button(|button| {
+        button
+            .custom_id(CANCEL_DRAFT_ID)
+            .label(i18n::text(locale, "button_cancel"))
+            .style(ButtonStyle::Danger)
+    });
+    row
+}
+
+fn create_unschedule_button(locale: &str) -> CreateButton {
+    let mut button = CreateButton::default();
+    button
+        .custom_id(UNSCHEDULE_ID)
+        .label(i18n::text(locale, "button_unschedule"))
+        .style(ButtonStyle::Danger);
+    button
+}
+
+fn create_my_vote_button(locale: &str) -> CreateButton {
+    let mut button = CreateButton::default();
+    button
+        .custom_id(MY_VOTE_ID)
+        .label(i18n::text(locale, "button_my_vote"))
+        .style(ButtonStyle::Secondary);
+    button
+}
+
+/// Describes the user's current vote
+fn create_my_vote(poll_data: &PollData, user_id: UserId, locale: &str) -> String {
+    match poll_data.votes.get(&user_id) {
+        Some(votes) if poll_data.is_abstention(votes) => {
+            i18n::text(locale, "my_vote_abstained").to_string()
+        }
+        Some(votes) if poll_data.kind == PollKind::Ranked => {
+            let ranking = votes
+                .iter()
+                .enumerate()
+                .map(|(i, vote)| {
+                    let option = poll_data.option(vote).unwrap_or(vote);
+                    format!("{}. {}", i + 1, sanitize(option))
+                })
+                .collect::<Vec<_>>();
+            i18n::format(
+                locale,
+                "my_vote_ranking",
+                &[("ranking", &ranking.join(", "))],
+            )
+        }
+        Some(votes) => {
+            // list the votes in the same order as the options
+            let votes = poll_data
+                .options
+                .iter()
+                .filter(|option| votes.iter().any(|v| v == option_id(option)))
+                .map(|option| sanitize(option))
+                .collect::<Vec<_>>();
+            i18n::format(locale, "my_vote", &[("votes", &votes.join(", "))])
+        }
+        None => i18n::text(locale, "my_vote_none").to_string(),
+    }
+}
+
+/// Creates the buttons of the user's panel, with the options they voted for highlighted
+fn create_my_vote_components(
+    poll_data: &PollData,
+    id: InteractionId,
+    user_id: UserId,
+    locale: &str,
+) -> Vec<CreateActionRow> {
+    let votes = poll_data.votes.get(&user_id).cloned().unwrap_or_default();
+    let voted = |vote_id: &str| votes.iter().any(|v| v == vote_id);
+    let style = |voted: bool| {
+        if voted {
+            ButtonStyle::Success
+        } else {
+            ButtonStyle::Secondary
+        }
+    };
+    let mut buttons = poll_data
+        .sorted_options()
+        .into_iter()
+        .map(|option| {
+            let position = poll_data.position(option);
+            let (emoji, text) = split_emoji(option);
+            let mut button = CreateButton::default();
+            button
+                .custom_id(format!("{SWITCH_PREFIX}{id}:{position}"))
+                .label(text)
+                .style(style(voted(text)))
+                // users can still take back their vote for a full option
+                .disabled(poll_data.is_full(option) && !voted(text));
+            if let Some(emoji) = emoji {
+                button.emoji(emoji);
+            }
+            button
+        })
+        .collect::<Vec<_>>();
+    if poll_data.abstain {
+        let mut button = CreateButton::default();
+        button
+            .custom_id(format!("{SWITCH_PREFIX}{id}:{ABSTAIN_ID}"))
+            .label(i18n::text(locale, "button_abstain"))
+            .style(style(voted(ABSTAIN_ID)));
+        buttons.push(button);
+    }
+    buttons.truncate(MAX_OPTIONS);
+    buttons
+        .chunks_mut(BUTTONS_PER_ROW)
+        .map(|buttons| {
+            let mut row = CreateActionRow::default();
+            for button in buttons {
+                if poll_data.closed {
+                    button.disabled(true);
+                }
+                row.add_button(button.clone());
+            }
+            row
+        })
+        .collect()
+}
+
+fn create_voters_button(locale: &str) -> CreateButton {
+    let mut button = CreateButton::default();
+    button
+        .custom_id(VOTERS_ID)
+        .label(i18n::text(locale, "button_see_voters"))
+        .style(ButtonStyle::Secondary);
+    button
+}
+
+fn create_add_option_button(locale: &str) -> CreateButton {
+    let mut button = CreateButton::default();
+    button
+        .custom_id(ADD_OPTION_ID)
+        .label(i18n::text(locale, "button_add_option"))
+        .style(ButtonStyle::Secondary);
+    button
+}
+
+fn create_abstain_button(poll_data: &PollData) -> CreateButton {
+    let mut button = CreateButton::default();
+    button
+        .custom_id(format!("{OPTION_PREFIX}{ABSTAIN_ID}"))
+        .label(create_label(
+            poll_data,
+            i18n::text(&poll_data.locale, "button_abstain"),
+            ABSTAIN_ID,
+        ))
+        .style(ButtonStyle::Secondary);
+    button
+}
+
+/// Labels the option's button or select menu option with its votes, unless they're hidden
+fn create_label(poll_data: &PollData, text: &str, vote_id: &str) -> String {
+    if poll_data.results_hidden() {
+        text.to_string()
+    } else {
+        format!("{text}: {}", poll_data.format_votes(vote_id))
+    }
+}
+
+/// Splits a leading emoji like in `🍕 Pizza` or `<:pizza:123> Pizza` from the option's text
+fn split_emoji(option: &str) -> (Option<ReactionType>, &str) {
+    let Some((emoji, text)) = option.split_once(char::is_whitespace) else {
+        return (None, option);
+    };
+    let text = text.trim_start();
+    // custom emoji are formatted as <:name:id>, or <a:name:id> if animated
+    let is_custom = emoji.starts_with('<') && emoji.ends_with('>');
+    // there's no simple way to recognise unicode emoji, but they can't contain letters or digits
+    let is_unicode = emoji.chars().all(|c| !c.is_ascii() && !c.is_alphanumeric());
+    if text.is_empty() || !(is_custom || is_unicode) {
+        return (None, option);
+    }
+    match ReactionType::try_from(emoji) {
+        Ok(emoji) => (Some(emoji), text),
+        Err(_) => (None, option),
+    }
+}
+
+/// The option's text without the emoji, used to identify the option
+fn option_id(option: &str) -> &str {
+    split_emoji(option).1
+}
+
+/// Escapes the option's Markdown and breaks its mentions, so that it's shown as it was entered
+/// in message content and embeds instead of changing their formatting or pinging anyone. Button
+/// labels and select menu options are shown literally, so they use the option as is
+fn sanitize(option: &str) -> String {
+    let (emoji, text) = split_emoji(option);
+    // the emoji's name can contain underscores, which can't be escaped without breaking it
+    let mut sanitized = match emoji {
+        Some(_) => option[..option.len() - text.len()].to_string(),
+        None => String::new(),
+    };
+    for c in text.chars() {
+        match c {
+            '\\' | '*' | '_' | '~' | '`' | '|' | '>' | '#' | '-' | '[' | ']' | ':' | '<' => {
+                sanitized.push('\\');
+                sanitized.push(c);
+            }
+            // a zero-width space keeps @everyone, @here and user and role mentions from pinging
+            '@' => sanitized.push_str("@\u{200B}"),
+            c => sanitized.push(c),
+        }
+    }
+    sanitized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn parse(input: &str) -> Result<ParsedOptions, PollError> {
+        parse_options(input, MAX_OPTIONS, false, false, SortOrder::Alpha)
+    }
+
+    #[test]
+    fn parses_options() {
+        let parsed = parse("b,a,c").unwrap();
+        assert_eq!(parsed.options, ["a", "b", "c"]);
+        assert!(parsed.caps.is_empty());
+        assert_eq!(parsed.duplicates, 0);
+    }
+
+    #[test]
+    fn rejects_empty_options() {
+        assert!(matches!(parse(""), Err(PollError::NoOptions)));
+        assert!(matches!(parse(" , ,,"), Err(PollError::NoOptions)));
+    }
+
+    #[test]
+    fn rejects_invisible_options() {
+        for invisible in [
+            "\u{200B}",
+            "\u{200B}\u{200C}\u{200D}",
+            " \u{FEFF} ",
+            "\u{2060}\u{00A0}\u{3000}",
+            "\u{3164}",
+            "\u{202E}\u{2066}",
+            "\u{FE0F}\u{E0041}",
+        ] {
+            assert!(
+                matches!(
+                    parse(&format!("a,{invisible}")),
+                    Err(PollError::InvisibleOption)
+                ),
+                "{invisible:?} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn accepts_options_with_invisible_characters_around_text() {
+        let parsed = parse("\u{200B}a\u{200B},b").unwrap();
+        assert_eq!(parsed.options.len(), 2);
+        // emoji sequences contain zero-width joiners and variation selectors
+        assert!(parse("👩\u{200D}💻,❤\u{FE0F}").is_ok());
+        assert!(parse("日本語,ä,Ω").is_ok());
+    }
+
+    #[test]
+    fn ignores_trailing_commas() {
+        assert_eq!(parse("a,b,").unwrap().options, ["a", "b"]);
+        assert_eq!(parse(",a,,b,,").unwrap().options, ["a", "b"]);
+    }
+
+    #[test]
+    fn trims_whitespace() {
+        assert_eq!(parse("  a ,\tb  \n c").unwrap().options, ["a", "b", "c"]);
+        assert_eq!(
+            parse("ice cream, pizza").unwrap().options,
+            ["ice cream", "pizza"]
+        );
+    }
+
+    #[test]
+    fn removes_duplicates() {
+        let parsed = parse("a,a,a").unwrap();
+        assert_eq!(parsed.options, ["a"]);
+        assert_eq!(parsed.duplicates, 2);
+        // options that only differ by emoji are the same option
+        let parsed = parse("🍕 pizza,pizza,b").unwrap();
+        assert_eq!(parsed.options.len(), 2);
+        assert_eq!(parsed.duplicates, 1);
+    }
+
+    #[test]
+    fn rejects_duplicates_when_strict() {
+        assert!(matches!(
+            parse_options("a,a,a", MAX_OPTIONS, true, false, SortOrder::Alpha),
+            Err(PollError::DuplicateOptions(2))
+        ));
+        assert!(parse_options("a,b", MAX_OPTIONS, true, false, SortOrder::Alpha).is_ok());
+    }
+
+    #[test]
+    fn rejects_too_many_options() {
+        let options = (0..MAX_OPTIONS + 1)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            parse(&options.join(",")),
+            Err(PollError::TooManyOptions(26, 25))
+        ));
+        assert!(parse(&options[..MAX_OPTIONS].join(",")).is_ok());
+        // duplicates don't count towards the limit
+        assert!(parse_options("a,b,a", 2, false, false, SortOrder::Alpha).is_ok());
+        assert!(matches!(
+            parse_options("a,b,c", 2, false, false, SortOrder::Alpha),
+            Err(PollError::TooManyOptions(3, 2))
+        ));
+    }
+
+    #[test]
+    fn rejects_too_long_options() {
+        let longest = "a".repeat(MAX_OPTION_LENGTH);
+        assert!(parse(&longest).is_ok());
+        let too_long = "a".repeat(MAX_OPTION_LENGTH + 1);
+        assert!(matches!(
+            parse(&too_long),
+            Err(PollError::OptionTooLong(option)) if option == too_long
+        ));
+        // the capacity takes up room in the button label
+        assert!(matches!(
+            parse(&format!("{longest}[4]")),
+            Err(PollError::OptionTooLong(_))
+        ));
+    }
+
+    #[test]
+    fn parses_capacities() {
+        let parsed = parse("Carpool[4], Bus").unwrap();
+        assert_eq!(parsed.options, ["Bus", "Carpool"]);
+        assert_eq!(parsed.caps.get("Carpool"), Some(&4));
+    }
+
+    #[test]
+    fn parses_quoted_options() {
+        let parsed = parse(r#""Rock, Paper, Scissors", "Lizard" , Spock"#).unwrap();
+        assert_eq!(parsed.options, ["Lizard", "Rock, Paper, Scissors", "Spock"]);
+        let parsed = parse("“Rock, Paper”, Lizard").unwrap();
+        assert_eq!(parsed.options, ["Lizard", "Rock, Paper"]);
+        // quotes that don't start an option are part of it
+        let parsed = parse(r#"12" pizza, 16" pizza"#).unwrap();
+        assert_eq!(parsed.options, [r#"12" pizza"#, r#"16" pizza"#]);
+    }
+
+    #[test]
+    fn normalizes_options() {
+        assert_eq!(normalize_option(" Yes ", false), "yes");
+        assert_eq!(normalize_option("Ice   Cream", false), "ice cream");
+        assert_eq!(normalize_option("🍕 Pizza", false), "pizza");
+        assert_eq!(normalize_option("Yes", true), "Yes");
+        assert_eq!(normalize_option("Ice \tCream ", true), "Ice Cream");
+    }
+
+    #[test]
+    fn removes_duplicates_ignoring_case() {
+        let parsed = parse("Yes,yes ,No").unwrap();
+        // the first of the duplicates keeps its text
+        assert_eq!(parsed.options, ["No", "Yes"]);
+        assert_eq!(parsed.duplicates, 1);
+        let parsed = parse("ice  cream,Ice cream").unwrap();
+        assert_eq!(parsed.options, ["ice  cream"]);
+        assert!(matches!(
+            parse_options("Yes,yes", MAX_OPTIONS, true, false, SortOrder::Alpha),
+            Err(PollError::DuplicateOptions(1))
+        ));
+    }
+
+    #[test]
+    fn keeps_options_differing_by_case_when_case_sensitive() {
+        let parsed =
+            parse_options("Yes,yes ,No", MAX_OPTIONS, false, true, SortOrder::Alpha).unwrap();
+        assert_eq!(parsed.options, ["No", "Yes", "yes"]);
+        assert_eq!(parsed.duplicates, 0);
+        // whitespace is still ignored
+        let parsed = parse_options("yes,yes ", MAX_OPTIONS, false, true, SortOrder::Alpha).unwrap();
+        assert_eq!(parsed.duplicates, 1);
+    }
+
+    fn test_poll(kind: PollKind, abstain: bool, caps: HashMap<String, u32>) -> PollData {
+        PollData {
+            start_time: SystemTime::UNIX_EPOCH,
+            end_time: SystemTime::UNIX_EPOCH,
+            scheduled: false,
+            reminded: false,
+            pinned: false,
+            creator: UserId(1),
+            guild_id: None,
+            channel_id: ChannelId(1),
+            message_id: MessageId(1),
+            closed: false,
+            title: None,
+            image: None,
+            options: ["a", "b", "c", "d"].map(String::from).to_vec(),
+            kind,
+            anonymous: false,
+            hide_results: false,
+            thread_results: false,
+            quorum: None,
+            target_votes: None,
+            abstain,
+            role: None,
+            eligible: None,
+            ties: TiePolicy::Report,
+            sort: SortOrder::Alpha,
+            colors: ButtonColors::Primary,
+            open_additions: false,
+            case_sensitive: false,
+            verified_only: false,
+            ping: None,
+            locale: i18n::DEFAULT_LOCALE.to_string(),
+            votes: HashMap::new(),
+            caps,
+            weights: HashMap::new(),
+            voter_weights: HashMap::new(),
+            recovered_tallies: HashMap::new(),
+            tallies: HashMap::new(),
+            weighted_tallies: HashMap::new(),
+        }
+    }
+
+    /// The labels of the poll's buttons as they'd be shown in the message
+    fn button_labels(poll_data: &PollData) -> Vec<String> {
+        create_vote_components(poll_data)
+            .into_iter()
+            .flat_map(|row| match row.0.get("components") {
+                Some(Value::Array(components)) => components.clone(),
+                _ => vec![],
+            })
+            .filter_map(|component| component["label"].as_str().map(String::from))
+            .collect()
+    }
+
+    #[test]
+    fn changing_vote_moves_count_between_buttons() {
+        let mut poll_data = test_poll(PollKind::Single, false, HashMap::new());
+        poll_data.vote(UserId(1), "a", 1).unwrap();
+        poll_data.vote(UserId(2), "a", 1).unwrap();
+        assert_eq!(
+            button_labels(&poll_data)[..4],
+            ["a: 2", "b: 0", "c: 0", "d: 0"]
+        );
+
+        // the old option's count drops as soon as the vote changes
+        poll_data.vote(UserId(1), "b", 1).unwrap();
+        assert_eq!(
+            button_labels(&poll_data)[..4],
+            ["a: 1", "b: 1", "c: 0", "d: 0"]
+        );
+        poll_data.vote(UserId(2), "b", 1).unwrap();
+        assert_eq!(
+            button_labels(&poll_data)[..4],
+            ["a: 0", "b: 2", "c: 0", "d: 0"]
+        );
+    }
+
+    #[test]
+    fn renaming_option_moves_recovered_votes() {
+        let mut poll_data = test_poll(PollKind::Single, false, HashMap::new());
+        poll_data.recovered_tallies.insert("a".to_string(), 2);
+        poll_data.recount();
+        poll_data.rename(0, "z".to_string());
+        poll_data.vote(UserId(1), "b", 1).unwrap();
+        // recounting, like when the polls are loaded, mustn't revive the old option's votes
+        poll_data.recount();
+        assert_eq!(
+            button_labels(&poll_data)[..4],
+            ["z: 2", "b: 1", "c: 0", "d: 0"]
+        );
+        assert_eq!(poll_data.votes_for("a"), 0);
+    }
+
+    #[test]
+    fn reaching_target_closes_poll_even_when_batched() {
+        let mut poll_data = test_poll(PollKind::Single, false, HashMap::new());
+        poll_data.target_votes = Some(2);
+        poll_data.vote(UserId(1), "a", 1).unwrap();
+        assert_eq!(
+            record_vote(&mut poll_data, UserId(1), true).1,
+            VoteUpdate::Batched
+        );
+        assert_eq!(
+            record_vote(&mut poll_data, UserId(1), false).1,
+            VoteUpdate::Immediate
+        );
+        assert!(!poll_data.closed);
+
+        // the closing vote is shown and announced right away instead of waiting for the flusher
+        poll_data.vote(UserId(2), "b", 1).unwrap();
+        assert_eq!(
+            record_vote(&mut poll_data, UserId(2), true).1,
+            VoteUpdate::Closed
+        );
+        assert!(poll_data.closed);
+    }
+
+    #[test]
+    fn quorum_status_hides_leader_with_hidden_results() {
+        let mut poll_data = test_poll(PollKind::Single, false, HashMap::new());
+        poll_data.quorum = Some(1);
+        poll_data.vote(UserId(1), "a", 1).unwrap();
+        let leading = i18n::format(&poll_data.locale, "quorum_leading", &[("winner", &"a")]);
+        assert_eq!(create_quorum_status(&poll_data).unwrap(), leading);
+
+        poll_data.hide_results = true;
+        let reached = i18n::text(&poll_data.locale, "quorum_reached");
+        assert_eq!(create_quorum_status(&poll_data).unwrap(), reached);
+    }
+
+    #[test]
+    fn parses_weights() {
+        let weights = parse_weights("<@&1>=2, 3 = 100").unwrap();
+        assert_eq!(weights, HashMap::from([(RoleId(1), 2), (RoleId(3), 100)]));
+        assert!(matches!(
+            parse_weights("<@&1>=0"),
+            Err(PollError::InvalidWeights(_))
+        ));
+        assert!(matches!(
+            parse_weights("<@&1>"),
+            Err(PollError::InvalidWeights(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_large_weights() {
+        assert!(matches!(
+            parse_weights("<@&1>=101"),
+            Err(PollError::WeightTooHigh)
+        ));
+        assert!(matches!(
+            parse_weights("<@&1>=50000000"),
+            Err(PollError::WeightTooHigh)
+        ));
+        assert!(matches!(
+            parse_weights("<@&1>=99999999999"),
+            Err(PollError::WeightTooHigh)
+        ));
+        // the bar can't overflow even if the weighted votes add up to the most a u32 can hold
+        assert_eq!(create_bar(u32::MAX, u32::MAX), "████████ 100%");
+        assert_eq!(create_bar(u32::MAX / 2, u32::MAX), "████░░░░ 50%");
+    }
+
+    #[test]
+    fn component_ids_resolve_to_options() {
+        let mut poll_data = test_poll(PollKind::Single, false, HashMap::new());
+        poll_data.options[2] = format!("🍕 {}", "c".repeat(MAX_OPTION_LENGTH));
+        let custom_ids = create_vote_components(&poll_data)
+            .into_iter()
+            .flat_map(|row| match row.0.get("components") {
+                Some(Value::Array(components)) => components.clone(),
+                _ => vec![],
+            })
+            .filter_map(|component| component["custom_id"].as_str().map(String::from))
+            .filter_map(|custom_id| custom_id.strip_prefix(OPTION_PREFIX).map(String::from))
+            .collect::<Vec<_>>();
+        assert_eq!(custom_ids, ["0", "1", "2", "3"]);
+        assert_eq!(
+            poll_data.resolve_component_id("2"),
+            "c".repeat(MAX_OPTION_LENGTH)
+        );
+        // components from before the IDs were positions still work
+        assert_eq!(poll_data.resolve_component_id("b"), "b");
+        assert_eq!(poll_data.resolve_component_id(ABSTAIN_ID), ABSTAIN_ID);
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(30 * 60));
+        assert_eq!(
+            parse_duration(" 2h ").unwrap(),
+            Duration::from_secs(2 * 60 * 60)
+        );
+        assert_eq!(
+            parse_duration("1d").unwrap(),
+            Duration::from_secs(24 * 60 * 60)
+        );
+        assert_eq!(
+            parse_duration("1h30m").unwrap(),
+            Duration::from_secs(90 * 60)
+        );
+        assert_eq!(
+            parse_duration("1d 2h 3m 4s").unwrap(),
+            Duration::from_secs(((24 + 2) * 60 + 3) * 60 + 4)
+        );
+        assert_eq!(parse_duration("7d").unwrap(), MAX_DURATION);
+        assert_eq!(
+            parse_duration("1h0m").unwrap(),
+            Duration::from_secs(60 * 60)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        for duration in [
+            "", " ", "0s", "0h0m", "90", "h", "1x", "1.5h", "-1h", "1h30", "30m1h", "1h1h", "1H",
+            "1 h", "1h,30m",
+        ] {
+            assert!(
+                matches!(parse_duration(duration), Err(PollError::InvalidDuration(d)) if d == duration),
+                "{duration:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_too_long_durations() {
+        for duration in [
+            "7d1s",
+            "169h",
+            "99999999999999999999s",
+            &format!("{}d", u64::MAX / 60),
+            &format!("{}s", u64::MAX),
+            &format!("1d{}s", u64::MAX - 1),
+        ] {
+            assert!(
+                matches!(parse_duration(duration), Err(PollError::DurationTooLong)),
+                "{duration:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn formatted_durations_parse_back() {
+        for secs in [1, 59, 60, 90 * 60, 60 * 60, 36 * 60 * 60, 7 * 24 * 60 * 60] {
+            let duration = Duration::from_secs(secs);
+            assert_eq!(
+                parse_duration(&format_duration(duration)).unwrap(),
+                duration
+            );
+        }
+    }
+
+    #[test]
+    fn sanitizes_options() {
+        assert_eq!(sanitize("Pizza"), "Pizza");
+        assert_eq!(sanitize("**bold**"), "\\*\\*bold\\*\\*");
+        assert_eq!(sanitize("__underline__"), "\\_\\_underline\\_\\_");
+        assert_eq!(sanitize("~~strike~~"), "\\~\\~strike\\~\\~");
+        assert_eq!(sanitize("||spoiler||"), "\\|\\|spoiler\\|\\|");
+        assert_eq!(sanitize("`code`"), "\\`code\\`");
+        assert_eq!(sanitize("# heading"), "\\# heading");
+        assert_eq!(sanitize("> quote"), "\\> quote");
+        assert_eq!(
+            sanitize("[link](https://example.com)"),
+            "\\[link\\](https\\://example.com)"
+        );
+        // escapes can't be undone with a backslash in the option
+        assert_eq!(sanitize("\\*"), "\\\\\\*");
+        // emoji in the option's text are escaped, but not the option's own emoji
+        assert_eq!(sanitize("<:my_emoji:123> a_b"), "<:my_emoji:123> a\\_b");
+        assert_eq!(sanitize("🍕 *Pizza*"), "🍕 \\*Pizza\\*");
+    }
+
+    #[test]
+    fn sanitized_options_cant_mention() {
+        for option in [
+            "@everyone",
+            "@here",
+            "<@123>",
+            "<@!123>",
+            "<@&123>",
+            "hi @everyone",
+        ] {
+            let sanitized = sanitize(option);
+            // every @ is followed by a zero-width space
+            assert!(
+                sanitized
+                    .split('@')
+                    .skip(1)
+                    .all(|rest| rest.starts_with('\u{200B}')),
+                "{sanitized:?}"
+            );
+        }
+
+        let mut poll_data = test_poll(PollKind::Single, false, HashMap::new());
+        poll_data.options = vec!["@everyone".to_string(), "**b**".to_string()];
+        poll_data.vote(UserId(1), "@everyone", 1).unwrap();
+        poll_data.closed = true;
+        for content in [
+            create_results(&poll_data),
+            create_announcement(&poll_data),
+            create_description(&poll_data),
+        ] {
+            assert!(!content.contains("@everyone"), "{content:?}");
+            assert!(!content.contains("**b**"), "{content:?}");
+        }
+    }
+
+    #[test]
+    fn help_fits_in_messages() {
+        let config = crate::config::Config {
+            discord_token: String::new(),
+            application_id: 1,
+            guild_commands: false,
+            polls_file: PathBuf::new(),
+            settings_file: PathBuf::new(),
+            history_file: None,
+            cleaner_interval: Duration::from_secs(60),
+            cleaner_dry_run: false,
+            reminder: None,
+            owner_id: None,
+            recovery_channel: None,
+            health_port: None,
+            poll: Config {
+                default_duration: Duration::from_secs(60),
+                max_polls_per_user: 1,
+                cooldown: Duration::ZERO,
+                max_options: MAX_OPTIONS,
+                min_account_age: Duration::ZERO,
+                vote_batch: None,
+                max_active_polls: None,
+            },
+        };
+        let mut commands = crate::command::Commands::default();
+        register(&mut commands, Arc::new(config));
+        let mut definitions = CreateApplicationCommands::default();
+        commands.definitions(&mut definitions);
+
+        let help = create_help(&definitions);
+        assert!(!help.is_empty());
+        for part in &help {
+            assert!(part.chars().count() <= MAX_CONTENT_LENGTH, "{part}");
+        }
+        // nothing is lost when splitting
+        let joined = help.join("\n");
+        assert!(joined.contains("/pollextend"));
+        assert!(joined.contains("case_sensitive"));
+    }
+
+    #[test]
+    fn splits_text_between_lines() {
+        assert_eq!(split_lines("a\nb\nc", 3), ["a\nb", "c"]);
+        assert_eq!(split_lines("abc\nd", 3), ["abc", "d"]);
+        assert_eq!(split_lines("a\n\nb", 10), ["a\n\nb"]);
+        assert!(split_lines("", 10).is_empty());
+    }
+
+    #[test]
+    fn truncates_long_text() {
+        assert_eq!(truncate("short".to_string(), 20), "short");
+        let truncated = truncate("a".repeat(30), 20);
+        assert_eq!(truncated.chars().count(), 20);
+        assert!(truncated.ends_with(TRUNCATED));
+    }
+
+    #[test]
+    fn long_polls_fit_in_messages() {
+        let mut poll_data = test_poll(PollKind::Single, true, HashMap::new());
+        poll_data.title = Some("t".repeat(MAX_TITLE_LENGTH.into()));
+        // more and longer options than can be entered, with custom emoji, which take up more room
+        poll_data.options = (0..30)
+            .map(|i| format!("<:emoji:{i:018}> {i:02}{}", "o".repeat(MAX_OPTION_LENGTH)))
+            .collect();
+        // every option is tied, so the announcement lists all of them
+        let options = poll_data.options.clone();
+        for (user, option) in options.iter().enumerate() {
+            poll_data.vote(UserId(user as u64 + 1), option, 1).unwrap();
+        }
+        poll_data.closed = true;
+
+        assert!(create_results(&poll_data).chars().count() <= MAX_CONTENT_LENGTH);
+        assert!(create_announcement(&poll_data).chars().count() <= MAX_CONTENT_LENGTH);
+        assert!(create_description(&poll_data).chars().count() <= MAX_DESCRIPTION_LENGTH);
+        poll_data.kind = PollKind::Ranked;
+        assert!(create_description(&poll_data).chars().count() <= MAX_DESCRIPTION_LENGTH);
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        // clicking the button of an option, or the abstain button
+        Click {
+            user: u64,
+            vote_id: String,
+            weight: u32,
+        },
+        // choosing options in the select menu, an empty selection retracts the user's votes
+        Select {
+            user: u64,
+            values: Vec<String>,
+            weight: u32,
+        },
+    }
+
+    fn kind() -> impl Strategy<Value = PollKind> {
+        prop_oneof![
+            Just(PollKind::Single),
+            Just(PollKind::Multi),
+            Just(PollKind::Ranked)
+        ]
+    }
+
+    fn vote_id() -> impl Strategy<Value = String> {
+        // includes an ID that isn't an option, like from an outdated button
+        prop::sample::select(vec!["a", "b", "c", "d", ABSTAIN_ID, "e"]).prop_map(String::from)
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            (1..6_u64, vote_id(), 1..4_u32).prop_map(|(user, vote_id, weight)| Op::Click {
+                user,
+                vote_id,
+                weight
+            }),
+            (1..6_u64, prop::collection::vec(vote_id(), 0..4), 1..4_u32).prop_map(
+                |(user, values, weight)| Op::Select {
+                    user,
+                    values,
+                    weight
+                }
+            ),
+        ]
+    }
+
+    fn caps() -> impl Strategy<Value = HashMap<String, u32>> {
+        prop::collection::hash_map(
+            prop::sample::select(vec!["a", "b"]).prop_map(String::from),
+            1..3_u32,
+            0..2,
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn tallies_match_votes(
+            kind in kind(),
+            abstain in any::<bool>(),
+            caps in caps(),
+            ops in prop::collection::vec(op(), 0..40),
+        ) {
+            let mut poll_data = test_poll(kind, abstain, caps);
+            for op in ops {
+                // votes for full options are rejected, which mustn't change anything
+                let _ = match op {
+                    Op::Click { user, vote_id, weight } => {
+                        poll_data.vote(UserId(user), &vote_id, weight)
+                    }
+                    Op::Select { user, values, weight } => {
+                        poll_data.select(UserId(user), &values, weight)
+                    }
+                };
+
+                let mut ids = poll_data.options.clone();
+                if abstain {
+                    ids.push(ABSTAIN_ID.to_string());
+                }
+                let selections = poll_data
+                    .votes
+                    .values()
+                    .map(|votes| poll_data.counted(votes).len() as u32)
+                    .sum::<u32>();
+                let counted = ids.iter().map(|id| poll_data.voters_for(id)).sum::<u32>();
+                prop_assert_eq!(counted, selections);
+                let weighted_selections = poll_data
+                    .votes
+                    .iter()
+                    .map(|(user_id, votes)| {
+                        poll_data.counted(votes).len() as u32 * poll_data.weight_of(*user_id)
+                    })
+                    .sum::<u32>();
+                let weighted = ids.iter().map(|id| poll_data.votes_for(id)).sum::<u32>();
+                prop_assert_eq!(weighted, weighted_selections);
+
+                // the maintained tallies match counting them from scratch
+                let (tallies, weighted_tallies) =
+                    (poll_data.tallies.clone(), poll_data.weighted_tallies.clone());
+                poll_data.recount();
+                prop_assert_eq!(&tallies, &poll_data.tallies);
+                prop_assert_eq!(&weighted_tallies, &poll_data.weighted_tallies);
+
+                for votes in poll_data.votes.values() {
+                    // users without votes are removed instead of being left with none
+                    prop_assert!(!votes.is_empty());
+                    prop_assert!(votes.iter().all(|vote| ids.contains(vote)));
+                    if kind == PollKind::Single || poll_data.is_abstention(votes) {
+                        prop_assert_eq!(votes.len(), 1);
+                    }
+                }
+                for (option, cap) in &poll_data.caps {
+                    prop_assert!(poll_data.voters_for(option) <= *cap);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,946 @@
+//! The poll commands.
+
+use super::{
+    create_help, ADD_OPTION_ID, CANCEL_DRAFT_ID, CLONE_COMMAND, CLOSE_COMMAND, COMMAND,
+    CONFIG_COMMAND, EDIT_COMMAND, EXPORT_COMMAND, EXTEND_COMMAND, HELP_COMMAND, LIST_COMMAND,
+    MAX_OPTIONS, MAX_TITLE_LENGTH, MY_VOTE_COMMAND, MY_VOTE_ID, POST_DRAFT_ID, PURGE_COMMAND,
+    REFRESH_COMMAND, RELOAD_COMMAND, RESULTS_COMMAND, STATS_COMMAND, SWITCH_PREFIX,
+    TRANSFER_COMMAND, UNSCHEDULE_ID, VOTERS_COMMAND, VOTERS_ID,
+};
+use crate::{
+    command::{Commands, SlashCommand},
+    config::Config,
+};
+use serenity::{
+    async_trait,
+    builder::{CreateApplicationCommand, CreateApplicationCommands},
+    model::{
+        application::{
+            command::CommandOptionType,
+            interaction::{
+                application_command::ApplicationCommandInteraction,
+                message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
+                MessageInteraction,
+            },
+        },
+        id::UserId,
+        Permissions,
+    },
+    prelude::*,
+};
+use std::sync::Arc;
+
+/// Registers the poll commands
+pub fn register(commands: &mut Commands, config: Arc<Config>) {
+    let (owner_id, guild_commands) = (config.owner_id, config.guild_commands);
+    commands.register(Poll {
+        config: config.clone(),
+    });
+    commands.register(ClonePoll {
+        config: config.clone(),
+    });
+    commands.register(Close);
+    commands.register(Edit);
+    commands.register(Refresh);
+    commands.register(Extend);
+    commands.register(Transfer);
+    commands.register(MyVote);
+    commands.register(Voters);
+    commands.register(Results);
+    commands.register(List);
+    commands.register(Export);
+    commands.register(Configure { config });
+    commands.register(Stats { owner_id });
+    commands.register(Purge { owner_id });
+    // the help is created from the definitions of the other commands
+    let mut definitions = CreateApplicationCommands::default();
+    commands.definitions(&mut definitions);
+    commands.register(Help {
+        help: create_help(&definitions),
+    });
+    // reloading registers every command, including itself
+    let mut reload = Reload {
+        owner_id,
+        guild_commands,
+        definitions: CreateApplicationCommands::default(),
+    };
+    let mut definitions = CreateApplicationCommands::default();
+    commands.definitions(&mut definitions);
+    definitions.create_application_command(|definition| reload.create(definition));
+    reload.definitions = definitions;
+    commands.register(reload);
+}
+
+pub struct Poll {
+    config: Arc<Config>,
+}
+
+#[async_trait]
+impl SlashCommand for Poll {
+    fn name(&self) -> &'static str {
+        COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(COMMAND)
+            .description("A simple poll command.")
+            .create_option(|option| {
+                option
+                    .name("options")
+                    .kind(CommandOptionType::String)
+                    .description("Comma-separated list of options.")
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("title")
+                    .kind(CommandOptionType::String)
+                    .description("The question being asked.")
+                    .max_length(MAX_TITLE_LENGTH)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("image")
+                    .kind(CommandOptionType::String)
+                    .description("Link to an image shown in the poll.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("duration")
+                    .kind(CommandOptionType::String)
+                    .description("How long the poll stays open, e.g. 30m, 2h or 1d.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("ends")
+                    .kind(CommandOptionType::String)
+                    .description("When the poll closes, e.g. 2024-06-01T18:00Z.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("starts")
+                    .kind(CommandOptionType::String)
+                    .description("When the poll is posted, e.g. 2h or 2024-06-01T12:00Z.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("multi")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Allow voting for several options.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("ranked")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Rank the options, deciding the winner by instant-runoff.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("anonymous")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Never reveal who voted for what.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("hide_results")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Hide the votes until the poll closes.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("thread_results")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Post the results in a thread off the poll once it closes.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("quorum")
+                    .kind(CommandOptionType::Integer)
+                    .description("How many people need to vote for the poll to be valid.")
+                    .min_int_value(1)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("target_votes")
+                    .kind(CommandOptionType::Integer)
+                    .description("Close the poll as soon as this many people have voted.")
+                    .min_int_value(1)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("abstain")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Add a button for abstaining without voting for an option.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("role")
+                    .kind(CommandOptionType::Role)
+                    .description("Only allow members with this role to vote.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("verified_only")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Only allow established accounts that passed screening to vote.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("weights")
+                    .kind(CommandOptionType::String)
+                    .description("How much the votes of roles count, e.g. @Mod=2, @Admin=3.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("ties")
+                    .kind(CommandOptionType::String)
+                    .description("How the winner is decided if options are tied.")
+                    .add_string_choice("Report the tie", "report")
+                    .add_string_choice("First listed option wins", "first")
+                    .add_string_choice("Require a runoff", "runoff")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("sort")
+                    .kind(CommandOptionType::String)
+                    .description("The order the options are shown in.")
+                    .add_string_choice("Alphabetical", "alpha")
+                    .add_string_choice("As entered", "entered")
+                    .add_string_choice("Most votes first", "votes")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("additions")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Let voters add their own options.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("ping")
+                    .kind(CommandOptionType::Role)
+                    .description("Mention this role when the poll is posted.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("pin")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Pin the poll until it closes.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("important")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Preview the poll and confirm it before it's posted.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("strict")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Reject the poll if the options contain duplicates.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("case_sensitive")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Treat options that only differ by case as different options.")
+                    .required(false)
+            })
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::start(ctx, command, &self.config.poll).await
+    }
+
+    async fn component(
+        &self,
+        ctx: &Context,
+        interaction: &MessageComponentInteraction,
+        message_interaction: &MessageInteraction,
+    ) -> anyhow::Result<()> {
+        poll_component(ctx, interaction, message_interaction, &self.config).await
+    }
+
+    async fn modal(
+        &self,
+        ctx: &Context,
+        interaction: &ModalSubmitInteraction,
+        message_interaction: &MessageInteraction,
+    ) -> anyhow::Result<()> {
+        super::add_option(ctx, interaction, message_interaction, &self.config.poll).await
+    }
+}
+
+/// Handles the components of poll messages, which are posted by both the poll and clone commands
+async fn poll_component(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    message_interaction: &MessageInteraction,
+    config: &Config,
+) -> anyhow::Result<()> {
+    match interaction.data.custom_id.as_str() {
+        POST_DRAFT_ID | CANCEL_DRAFT_ID => {
+            super::confirm(ctx, interaction, message_interaction).await
+        }
+        UNSCHEDULE_ID => super::unschedule(ctx, interaction, message_interaction).await,
+        VOTERS_ID => super::show_voters(ctx, interaction, message_interaction).await,
+        MY_VOTE_ID => super::show_my_vote(ctx, interaction, message_interaction).await,
+        custom_id if custom_id.starts_with(SWITCH_PREFIX) => {
+            super::switch_vote(ctx, interaction, message_interaction, &config.poll).await
+        }
+        ADD_OPTION_ID => {
+            super::prompt_option(ctx, interaction, message_interaction, &config.poll).await
+        }
+        _ => super::vote(ctx, interaction, message_interaction, &config.poll).await,
+    }
+}
+
+pub struct ClonePoll {
+    config: Arc<Config>,
+}
+
+#[async_trait]
+impl SlashCommand for ClonePoll {
+    fn name(&self) -> &'static str {
+        CLONE_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(CLONE_COMMAND)
+            .description("Starts a new poll with the same options and settings as an earlier one.")
+            .create_option(|option| {
+                option
+                    .name("poll")
+                    .kind(CommandOptionType::String)
+                    .description("Link or ID of the poll message.")
+                    .required(true)
+            })
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::clone_poll(ctx, command, &self.config.poll).await
+    }
+
+    async fn component(
+        &self,
+        ctx: &Context,
+        interaction: &MessageComponentInteraction,
+        message_interaction: &MessageInteraction,
+    ) -> anyhow::Result<()> {
+        poll_component(ctx, interaction, message_interaction, &self.config).await
+    }
+
+    async fn modal(
+        &self,
+        ctx: &Context,
+        interaction: &ModalSubmitInteraction,
+        message_interaction: &MessageInteraction,
+    ) -> anyhow::Result<()> {
+        super::add_option(ctx, interaction, message_interaction, &self.config.poll).await
+    }
+}
+
+pub struct Close;
+
+#[async_trait]
+impl SlashCommand for Close {
+    fn name(&self) -> &'static str {
+        CLOSE_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(CLOSE_COMMAND)
+            .description("Closes a poll you started.")
+            .create_option(|option| {
+                option
+                    .name("poll")
+                    .kind(CommandOptionType::String)
+                    .description("Link or ID of the poll message.")
+                    .required(true)
+            })
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::close(ctx, command).await
+    }
+}
+
+pub struct Edit;
+
+#[async_trait]
+impl SlashCommand for Edit {
+    fn name(&self) -> &'static str {
+        EDIT_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(EDIT_COMMAND)
+            .description("Renames an option in a poll you started.")
+            .create_option(|option| {
+                option
+                    .name("poll")
+                    .kind(CommandOptionType::String)
+                    .description("Link or ID of the poll message.")
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("option")
+                    .kind(CommandOptionType::String)
+                    .description("The option to rename.")
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("new")
+                    .kind(CommandOptionType::String)
+                    .description("The new name of the option.")
+                    .required(true)
+            })
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::edit(ctx, command).await
+    }
+}
+
+pub struct Refresh;
+
+#[async_trait]
+impl SlashCommand for Refresh {
+    fn name(&self) -> &'static str {
+        REFRESH_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(REFRESH_COMMAND)
+            .description("Restores the results and buttons of a poll you started.")
+            .create_option(|option| {
+                option
+                    .name("poll")
+                    .kind(CommandOptionType::String)
+                    .description("Link or ID of the poll message.")
+                    .required(true)
+            })
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::refresh(ctx, command).await
+    }
+}
+
+pub struct Extend;
+
+#[async_trait]
+impl SlashCommand for Extend {
+    fn name(&self) -> &'static str {
+        EXTEND_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(EXTEND_COMMAND)
+            .description("Gives voters more time in a poll you started.")
+            .create_option(|option| {
+                option
+                    .name("poll")
+                    .kind(CommandOptionType::String)
+                    .description("Link or ID of the poll message.")
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("duration")
+                    .kind(CommandOptionType::String)
+                    .description("How much longer the poll stays open, e.g. 30m, 2h or 1d.")
+                    .required(true)
+            })
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::extend(ctx, command).await
+    }
+}
+
+pub struct Transfer;
+
+#[async_trait]
+impl SlashCommand for Transfer {
+    fn name(&self) -> &'static str {
+        TRANSFER_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(TRANSFER_COMMAND)
+            .description("Makes someone else the creator of a poll you started.")
+            .create_option(|option| {
+                option
+                    .name("poll")
+                    .kind(CommandOptionType::String)
+                    .description("Link or ID of the poll message.")
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("user")
+                    .kind(CommandOptionType::User)
+                    .description("The new creator of the poll.")
+                    .required(true)
+            })
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::transfer(ctx, command).await
+    }
+}
+
+pub struct MyVote;
+
+#[async_trait]
+impl SlashCommand for MyVote {
+    fn name(&self) -> &'static str {
+        MY_VOTE_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(MY_VOTE_COMMAND)
+            .description("Shows what you voted for in a poll, with buttons for changing your vote.")
+            .create_option(|option| {
+                option
+                    .name("poll")
+                    .kind(CommandOptionType::String)
+                    .description("Link or ID of the poll message.")
+                    .required(true)
+            })
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::my_vote(ctx, command).await
+    }
+}
+
+pub struct Voters;
+
+#[async_trait]
+impl SlashCommand for Voters {
+    fn name(&self) -> &'static str {
+        VOTERS_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(VOTERS_COMMAND)
+            .description("Shows who voted for each option in a poll.")
+            .create_option(|option| {
+                option
+                    .name("poll")
+                    .kind(CommandOptionType::String)
+                    .description("Link or ID of the poll message.")
+                    .required(true)
+            })
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::voters(ctx, command).await
+    }
+}
+
+pub struct Results;
+
+#[async_trait]
+impl SlashCommand for Results {
+    fn name(&self) -> &'static str {
+        RESULTS_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(RESULTS_COMMAND)
+            .description("Posts the current results of a poll.")
+            .create_option(|option| {
+                option
+                    .name("poll")
+                    .kind(CommandOptionType::String)
+                    .description("Link or ID of the poll message.")
+                    .required(true)
+            })
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::results(ctx, command).await
+    }
+}
+
+pub struct List;
+
+#[async_trait]
+impl SlashCommand for List {
+    fn name(&self) -> &'static str {
+        LIST_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(LIST_COMMAND)
+            .description("Lists the active polls in this channel.")
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::list(ctx, command).await
+    }
+}
+
+pub struct Export;
+
+#[async_trait]
+impl SlashCommand for Export {
+    fn name(&self) -> &'static str {
+        EXPORT_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(EXPORT_COMMAND)
+            .description("Exports the results of a poll as a file.")
+            .create_option(|option| {
+                option
+                    .name("poll")
+                    .kind(CommandOptionType::String)
+                    .description("Link or ID of the poll message.")
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("voters")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Include each user's votes, unless the poll is anonymous.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("format")
+                    .kind(CommandOptionType::String)
+                    .description("The format of the file (default: CSV).")
+                    .add_string_choice("CSV", "csv")
+                    .add_string_choice("JSON", "json")
+                    .required(false)
+            })
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::export(ctx, command).await
+    }
+}
+
+pub struct Configure {
+    config: Arc<Config>,
+}
+
+#[async_trait]
+impl SlashCommand for Configure {
+    fn name(&self) -> &'static str {
+        CONFIG_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(CONFIG_COMMAND)
+            .description("Sets this server's defaults for new polls, or shows them.")
+            // admins can allow other roles in the server's integration settings
+            .default_member_permissions(Permissions::MANAGE_GUILD)
+            .dm_permission(false)
+            .create_option(|option| {
+                option
+                    .name("duration")
+                    .kind(CommandOptionType::String)
+                    .description("How long polls stay open when they don't specify a duration, e.g. 30m, 2h or 1d.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("max_options")
+                    .kind(CommandOptionType::Integer)
+                    .description("How many options each poll can have.")
+                    .min_int_value(1)
+                    .max_int_value(MAX_OPTIONS)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("anonymous")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Whether polls are anonymous unless they specify otherwise.")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("colors")
+                    .kind(CommandOptionType::String)
+                    .description("The colors of the options' buttons in new polls.")
+                    .add_string_choice("Blurple", "primary")
+                    .add_string_choice("Grey", "secondary")
+                    .add_string_choice("Green", "success")
+                    .add_string_choice("Red", "danger")
+                    .add_string_choice("A different one for each option", "cycle")
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("reset")
+                    .kind(CommandOptionType::Boolean)
+                    .description("Restores the bot's defaults before applying the other options.")
+                    .required(false)
+            })
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::configure(ctx, command, &self.config).await
+    }
+}
+
+pub struct Stats {
+    // the user allowed to see the stats, if any
+    owner_id: Option<UserId>,
+}
+
+#[async_trait]
+impl SlashCommand for Stats {
+    fn name(&self) -> &'static str {
+        STATS_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(STATS_COMMAND)
+            .description("Shows usage statistics, only for the owner of the bot.")
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::stats(ctx, command, self.owner_id).await
+    }
+}
+
+pub struct Purge {
+    // the user allowed to close every poll, if any
+    owner_id: Option<UserId>,
+}
+
+#[async_trait]
+impl SlashCommand for Purge {
+    fn name(&self) -> &'static str {
+        PURGE_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(PURGE_COMMAND)
+            .description("Closes every active poll, only for the owner of the bot.")
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::purge(ctx, command, self.owner_id).await
+    }
+}
+
+pub struct Reload {
+    // the user allowed to reload the commands, if any
+    owner_id: Option<UserId>,
+    // whether the commands are registered in each guild rather than globally, like on startup
+    guild_commands: bool,
+    definitions: CreateApplicationCommands,
+}
+
+#[async_trait]
+impl SlashCommand for Reload {
+    fn name(&self) -> &'static str {
+        RELOAD_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(RELOAD_COMMAND)
+            .description("Registers the bot's commands again, only for the owner of the bot.")
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::reload(
+            ctx,
+            command,
+            self.owner_id,
+            self.guild_commands,
+            &self.definitions,
+        )
+        .await
+    }
+}
+
+pub struct Help {
+    // split into parts that each fit in a message
+    help: Vec<String>,
+}
+
+#[async_trait]
+impl SlashCommand for Help {
+    fn name(&self) -> &'static str {
+        HELP_COMMAND
+    }
+
+    fn create<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(HELP_COMMAND)
+            .description("Explains how to use the poll commands.")
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<()> {
+        super::respond_ephemeral_parts(ctx, command, &self.help).await
+    }
+}
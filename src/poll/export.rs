@@ -0,0 +1,147 @@
+//! Exporting poll results as files.
+
+use super::{option_id, PollData, PollKind, ABSTAIN_ID};
+use anyhow::Context as _;
+use serde::Serialize;
+use serenity::model::id::{ChannelId, MessageId, UserId};
+use std::{collections::HashMap, time::SystemTime};
+
+/// Creates a CSV file with the votes for each option, followed by each user's votes if requested
+/// and the poll isn't anonymous
+pub fn create_csv(poll_data: &PollData, voters: bool) -> String {
+    // weighted polls have a column for the weighted votes, abstentions aren't weighted
+    let weighted = poll_data.is_weighted();
+    let mut csv = if weighted {
+        "option,votes,weighted_votes\n".to_string()
+    } else {
+        "option,votes\n".to_string()
+    };
+    for option in &poll_data.options {
+        let votes = poll_data.voters_for(option);
+        if weighted {
+            let weighted_votes = poll_data.votes_for(option);
+            csv.push_str(&format!(
+                "{},{votes},{weighted_votes}\n",
+                escape_csv(option)
+            ));
+        } else {
+            csv.push_str(&format!("{},{votes}\n", escape_csv(option)));
+        }
+    }
+    if poll_data.abstain {
+        let abstentions = poll_data.abstentions();
+        if weighted {
+            csv.push_str(&format!("{ABSTAIN_ID},{abstentions},{abstentions}\n"));
+        } else {
+            csv.push_str(&format!("{ABSTAIN_ID},{abstentions}\n"));
+        }
+    }
+    if voters && !poll_data.anonymous {
+        csv.push_str("\nuser,vote\n");
+        // votes are listed in the order they were cast, which is the ranking for ranked polls
+        for (user_id, votes) in &poll_data.votes {
+            for vote in votes {
+                let option = poll_data.option(vote).unwrap_or(vote);
+                csv.push_str(&format!("{user_id},{}\n", escape_csv(option)));
+            }
+        }
+    }
+    csv
+}
+
+/// Quotes the field if it contains characters that have a special meaning in CSV
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// The exported form of a poll, kept separate from `PollData` so that the format stays stable
+#[derive(Serialize)]
+struct PollExport<'a> {
+    title: Option<&'a str>,
+    kind: &'static str,
+    creator: UserId,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    // unix timestamps in seconds
+    start_time: u64,
+    end_time: u64,
+    closed: bool,
+    anonymous: bool,
+    quorum: Option<u32>,
+    voter_count: usize,
+    options: Vec<OptionExport<'a>>,
+    abstentions: Option<u32>,
+    winners: Vec<&'a str>,
+    // left out for anonymous polls or if not requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    votes: Option<HashMap<UserId, Vec<&'a str>>>,
+}
+
+#[derive(Serialize)]
+struct OptionExport<'a> {
+    option: &'a str,
+    votes: u32,
+    // left out for polls without weights
+    #[serde(skip_serializing_if = "Option::is_none")]
+    weighted_votes: Option<u32>,
+    capacity: Option<u32>,
+}
+
+/// Creates a JSON file describing the poll and its results, including each user's votes if
+/// requested and the poll isn't anonymous
+pub fn create_json(poll_data: &PollData, voters: bool) -> anyhow::Result<String> {
+    let unix_secs = |time: SystemTime| {
+        time.duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    };
+    let options = poll_data
+        .options
+        .iter()
+        .map(|option| OptionExport {
+            option,
+            votes: poll_data.voters_for(option),
+            weighted_votes: poll_data.is_weighted().then(|| poll_data.votes_for(option)),
+            capacity: poll_data.caps.get(option_id(option)).copied(),
+        })
+        .collect();
+    let votes = (voters && !poll_data.anonymous).then(|| {
+        poll_data
+            .votes
+            .iter()
+            .map(|(user_id, votes)| {
+                let votes = votes
+                    .iter()
+                    .map(|vote| poll_data.option(vote).unwrap_or(vote))
+                    .collect();
+                (*user_id, votes)
+            })
+            .collect()
+    });
+    let export = PollExport {
+        title: poll_data.title.as_deref(),
+        kind: match poll_data.kind {
+            PollKind::Single => "single",
+            PollKind::Multi => "multi",
+            PollKind::Ranked => "ranked",
+        },
+        creator: poll_data.creator,
+        channel_id: poll_data.channel_id,
+        message_id: poll_data.message_id,
+        start_time: unix_secs(poll_data.start_time),
+        end_time: unix_secs(poll_data.end_time),
+        closed: poll_data.closed,
+        anonymous: poll_data.anonymous,
+        quorum: poll_data.quorum,
+        voter_count: poll_data.votes.len(),
+        options,
+        abstentions: poll_data.abstain.then(|| poll_data.abstentions()),
+        winners: poll_data.winners(),
+        votes,
+    };
+    serde_json::to_string_pretty(&export).context("failed to serialize poll")
+}
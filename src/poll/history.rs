@@ -0,0 +1,152 @@
+//! An optional record of every poll and vote in an SQLite database, for analysing them outside of
+//! the bot. Only available with the `sqlite` feature.
+
+use super::PollData;
+use serenity::model::id::{InteractionId, UserId};
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::{InteractionId, PollData, UserId};
+    use anyhow::Context as _;
+    use once_cell::sync::OnceCell;
+    use rusqlite::{params, Connection};
+    use std::{
+        path::Path,
+        sync::Mutex,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    const SCHEMA: &str = "
+        CREATE TABLE IF NOT EXISTS polls (
+            id INTEGER PRIMARY KEY,
+            guild_id INTEGER,
+            channel_id INTEGER NOT NULL,
+            message_id INTEGER NOT NULL,
+            creator INTEGER NOT NULL,
+            title TEXT,
+            kind TEXT NOT NULL,
+            options TEXT NOT NULL,
+            started_at INTEGER NOT NULL,
+            ends_at INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS votes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            poll_id INTEGER NOT NULL REFERENCES polls (id),
+            user_id INTEGER NOT NULL,
+            votes TEXT NOT NULL,
+            weight INTEGER NOT NULL,
+            voted_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS votes_poll_id ON votes (poll_id);
+    ";
+
+    static DATABASE: OnceCell<Mutex<Connection>> = OnceCell::new();
+
+    pub fn open(path: &Path) -> anyhow::Result<()> {
+        let connection =
+            Connection::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        connection
+            .execute_batch(SCHEMA)
+            .context("failed to create the history tables")?;
+        // only fails if the database is already open, in which case the new connection is dropped
+        let _ = DATABASE.set(Mutex::new(connection));
+        Ok(())
+    }
+
+    pub async fn record_poll(id: InteractionId, poll_data: &PollData) {
+        let kind = serde_json::to_value(poll_data.kind)
+            .ok()
+            .and_then(|kind| kind.as_str().map(String::from))
+            .unwrap_or_default();
+        let options = serde_json::to_string(&poll_data.options).unwrap_or_default();
+        let row = (
+            id.0 as i64,
+            poll_data.guild_id.map(|guild_id| guild_id.0 as i64),
+            poll_data.channel_id.0 as i64,
+            poll_data.message_id.0 as i64,
+            poll_data.creator.0 as i64,
+            poll_data.title.clone(),
+            kind,
+            options,
+            unix_secs(poll_data.start_time),
+            unix_secs(poll_data.end_time),
+        );
+        execute(move |connection| {
+            connection.execute(
+                "INSERT OR REPLACE INTO polls
+                    (id, guild_id, channel_id, message_id, creator, title, kind, options,
+                     started_at, ends_at)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![row.0, row.1, row.2, row.3, row.4, row.5, row.6, row.7, row.8, row.9],
+            )
+        })
+        .await;
+    }
+
+    pub async fn record_vote(id: InteractionId, user_id: UserId, votes: &[String], weight: u32) {
+        let row = (
+            id.0 as i64,
+            user_id.0 as i64,
+            serde_json::to_string(votes).unwrap_or_default(),
+            weight,
+            unix_secs(SystemTime::now()),
+        );
+        execute(move |connection| {
+            connection.execute(
+                "INSERT INTO votes (poll_id, user_id, votes, weight, voted_at)
+                    VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![row.0, row.1, row.2, row.3, row.4],
+            )
+        })
+        .await;
+    }
+
+    /// Runs the statement on a blocking thread if the database is open, logging any errors since
+    /// the history shouldn't stop polls from working
+    async fn execute(
+        statement: impl FnOnce(&Connection) -> rusqlite::Result<usize> + Send + 'static,
+    ) {
+        let Some(database) = DATABASE.get() else {
+            return;
+        };
+        let res = tokio::task::spawn_blocking(move || {
+            let connection = database.lock().unwrap_or_else(|err| err.into_inner());
+            statement(&connection)
+        })
+        .await;
+        match res {
+            Ok(Ok(_)) => {}
+            Ok(Err(err)) => tracing::error!("Failed to record history: {err}"),
+            Err(err) => tracing::error!("Failed to record history: {err}"),
+        }
+    }
+
+    fn unix_secs(time: SystemTime) -> i64 {
+        time.duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64
+    }
+}
+
+/// Opens or creates the database at the path, which polls and votes are recorded in from then on
+#[cfg(feature = "sqlite")]
+pub fn open(path: &std::path::Path) -> anyhow::Result<()> {
+    sqlite::open(path)
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub fn open(_path: &std::path::Path) -> anyhow::Result<()> {
+    anyhow::bail!("the history database requires the bot to be built with the sqlite feature")
+}
+
+/// Records the poll once it's been posted
+pub async fn record_poll(_id: InteractionId, _poll_data: &PollData) {
+    #[cfg(feature = "sqlite")]
+    sqlite::record_poll(_id, _poll_data).await;
+}
+
+/// Records the user's votes after they voted, an empty list if they retracted their vote
+pub async fn record_vote(_id: InteractionId, _user_id: UserId, _votes: &[String], _weight: u32) {
+    #[cfg(feature = "sqlite")]
+    sqlite::record_vote(_id, _user_id, _votes, _weight).await;
+}
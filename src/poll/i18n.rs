@@ -0,0 +1,563 @@
+//! Translations of the user-facing strings, looked up by the locale of the interaction.
+
+use once_cell::sync::Lazy;
+use std::{collections::HashMap, fmt::Display};
+
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+type Catalog = HashMap<&'static str, &'static str>;
+
+// catalogs by language, which Discord's locales like `en-US` or `de` start with
+static CATALOGS: Lazy<HashMap<&'static str, Catalog>> = Lazy::new(|| {
+    HashMap::from([
+        ("en", EN.iter().copied().collect()),
+        ("de", DE.iter().copied().collect()),
+    ])
+});
+
+/// Returns the string for the key in the locale's language, falling back to English
+pub fn text(locale: &str, key: &str) -> &'static str {
+    let language = locale.split('-').next().unwrap_or(locale);
+    [language, "en"]
+        .iter()
+        .filter_map(|language| CATALOGS.get(language)?.get(key))
+        .next()
+        .copied()
+        .unwrap_or_else(|| {
+            tracing::warn!("Missing translation for {key}");
+            ""
+        })
+}
+
+/// Returns the string for the key with its `{name}` placeholders replaced by the arguments.
+/// The placeholders are replaced in a single pass over the string, so that arguments like titles
+/// that contain something like a placeholder are left as they are
+pub fn format(locale: &str, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut rest = text(locale, key);
+    let mut text = String::with_capacity(rest.len());
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let arg = rest.find('}').and_then(|end| {
+            let (_, value) = args.iter().find(|(name, _)| *name == &rest[1..end])?;
+            Some((end, value))
+        });
+        match arg {
+            Some((end, value)) => {
+                text.push_str(&value.to_string());
+                rest = &rest[end + 1..];
+            }
+            // not a placeholder, or one without an argument
+            None => {
+                text.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+const EN: &[(&str, &str)] = &[
+    // errors
+    ("error_no_options", "You must provide at least one option."),
+    (
+        "error_invisible_option",
+        "Options must contain visible text, not just invisible characters.",
+    ),
+    (
+        "error_too_many_options",
+        "Too many options ({count}), this poll can have at most {max}.",
+    ),
+    (
+        "error_option_too_long",
+        "The option `{option}` is too long, options can be at most {max} characters.",
+    ),
+    (
+        "error_duplicate_options_one",
+        "The options contain 1 duplicate.",
+    ),
+    (
+        "error_duplicate_options",
+        "The options contain {count} duplicates.",
+    ),
+    (
+        "error_invalid_duration",
+        "Invalid duration `{duration}`, expected something like 30m, 2h, 1d or 1h30m.",
+    ),
+    (
+        "error_duration_too_long",
+        "The duration can be at most 7 days (7d).",
+    ),
+    (
+        "error_invalid_end_time",
+        "Invalid end time `{ends}`, expected something like 2024-06-01T18:00Z.",
+    ),
+    ("error_end_in_past", "The end time is in the past."),
+    (
+        "error_invalid_start_time",
+        "Invalid start time `{starts}`, expected something like 2h or 2024-06-01T18:00Z.",
+    ),
+    ("error_start_in_past", "The start time is in the past."),
+    (
+        "error_start_too_late",
+        "Polls can be scheduled to start at most 7 days from now.",
+    ),
+    (
+        "error_invalid_image",
+        "Invalid image `{image}`, expected a link starting with http:// or https://.",
+    ),
+    (
+        "error_invalid_weights",
+        "Invalid weights `{weights}`, expected something like @Mod=2, @Admin=3.",
+    ),
+    ("error_weight_too_high", "Weights can be at most {max}."),
+    (
+        "error_duration_and_end",
+        "A poll can't have both a duration and an end time.",
+    ),
+    ("error_poll_ended", "That poll has already ended."),
+    ("error_poll_closed", "This poll is closed."),
+    (
+        "error_multi_and_ranked",
+        "A poll can't be both multi-select and ranked.",
+    ),
+    (
+        "error_poll_not_found",
+        "Could not find an active poll with that message.",
+    ),
+    (
+        "error_not_creator",
+        "Only the creator of the poll can do that.",
+    ),
+    (
+        "error_too_many_polls",
+        "You can have at most {max} polls running at once, close one to start a new one.",
+    ),
+    (
+        "error_anonymous",
+        "The poll is anonymous, its voters can't be shown.",
+    ),
+    (
+        "error_results_hidden",
+        "The poll's results are hidden until it closes.",
+    ),
+    (
+        "error_reserved_option",
+        "`{option}` can't be used as an option in this poll.",
+    ),
+    (
+        "error_missing_role",
+        "You don't have permission to vote in this poll.",
+    ),
+    (
+        "error_unverified",
+        "Only accounts older than {days} days that have completed the server's membership screening can take part in this poll.",
+    ),
+    ("error_not_owner", "Only the owner of the bot can do that."),
+    (
+        "error_option_not_found",
+        "The poll doesn't have an option called `{option}`.",
+    ),
+    (
+        "error_option_exists",
+        "The poll already has an option called `{option}`.",
+    ),
+    (
+        "error_draft_expired",
+        "The preview has expired, start the poll again.",
+    ),
+    ("error_option_full", "`{option}` is full."),
+    (
+        "error_cooldown_one",
+        "Please wait 1 second before starting another poll here.",
+    ),
+    (
+        "error_cooldown",
+        "Please wait {secs} seconds before starting another poll here.",
+    ),
+    // poll message
+    ("poll", "Poll"),
+    ("thread_name", "Results: {title}"),
+    ("ends", "Ends"),
+    ("ended", "Ended"),
+    ("vote", "Vote:"),
+    ("final_results", "Poll closed, final results:"),
+    (
+        "attribution",
+        "Poll by <@{creator}> • started at <t:{started}:f>",
+    ),
+    (
+        "ranked_hint",
+        "Click the options in order of preference, first choices shown:",
+    ),
+    ("quorum_failed", "Poll failed to reach quorum."),
+    ("quorum_missing_one", "Needs 1 more vote to reach quorum."),
+    (
+        "quorum_missing",
+        "Needs {missing} more votes to reach quorum.",
+    ),
+    ("quorum_reached", "Quorum reached."),
+    ("quorum_leading", "Quorum reached. Leading: {winner}"),
+    ("winner", "Winner: {winner}"),
+    ("tie", "Tie between {winners}"),
+    ("tie_runoff", "Tie between {winners}, a runoff is needed"),
+    ("quorum_tied", "Quorum reached. Tied: {winners}"),
+    ("abstained", "Abstained: {count}"),
+    ("weighted_votes_one", "{votes} (1 voter)"),
+    ("weighted_votes", "{votes} ({count} voters)"),
+    ("total_votes_one", "Total votes: {total} (from 1 voter)"),
+    ("total_votes", "Total votes: {total} (from {voters} voters)"),
+    ("turnout", "{voters} of {eligible} eligible members voted."),
+    ("results_hidden", "Results hidden until poll closes."),
+    ("and_more", "…and {count} more"),
+    ("round", "Round {round}: {tallies}"),
+    ("eliminated", " — eliminated {options}"),
+    ("results_title", "Results for **{title}** ({link}):"),
+    ("results", "Results for {link}:"),
+    // components
+    ("button_abstain", "Abstain"),
+    ("button_see_voters", "See voters"),
+    ("button_my_vote", "My vote"),
+    ("my_vote", "You voted for: {votes}"),
+    ("my_vote_ranking", "Your ranking: {ranking}"),
+    ("my_vote_abstained", "You abstained."),
+    ("my_vote_none", "You haven't voted yet."),
+    ("button_post", "Post poll"),
+    ("button_cancel", "Cancel"),
+    ("button_unschedule", "Cancel scheduled poll"),
+    ("button_add_option", "Add option"),
+    ("add_option_title", "Add an option"),
+    ("add_option_label", "Option"),
+    ("choose_options", "Choose options"),
+    ("choose_option", "Choose an option"),
+    // replies and announcements
+    ("draft_posted", "Poll posted."),
+    ("posted_link", "Your poll was posted: {link}"),
+    ("draft_cancelled", "Poll cancelled."),
+    ("scheduled", "Poll scheduled to start {start}."),
+    ("unscheduled", "Scheduled poll cancelled."),
+    ("ended_no_votes", "Poll ended — no votes were cast."),
+    ("ended_winner", "Poll ended — winner: {winner}"),
+    ("ended_tie", "Poll ended — tie between {winners}"),
+    (
+        "ended_tie_runoff",
+        "Poll ended — tie between {winners}, a runoff is needed",
+    ),
+    ("reminder_one", "1 minute left to vote!"),
+    ("reminder", "{minutes} minutes left to vote!"),
+    ("duplicates_removed_one", "Removed 1 duplicate option."),
+    ("duplicates_removed", "Removed {count} duplicate options."),
+    ("poll_closed", "Poll closed."),
+    ("transferred", "<@{user}> is now the poll's creator."),
+    ("renamed", "Renamed `{old}` to `{new}`."),
+    ("poll_refreshed", "Poll refreshed."),
+    ("extended", "The poll now ends <t:{end}:R>, at <t:{end}:f>."),
+    ("voters_abstained", "Abstained"),
+    ("voters_none", "no votes"),
+    ("list_empty", "There are no active polls in this channel."),
+    ("list", "Active polls in this channel ({count}):"),
+    ("listing", "- {name}: {leading}, ends <t:{end}:R>"),
+    ("listing_hidden", "results hidden"),
+    ("listing_no_votes", "no votes yet"),
+    ("listing_leader", "{leader} leads"),
+    ("listing_tie", "{leaders} are tied"),
+    ("reloaded", "Registered {count} commands in this server."),
+    ("reloaded_global", "Registered {count} commands globally."),
+    ("reload_failed", "Failed to register the commands: {error}"),
+    (
+        "settings",
+        "Poll settings for this server:\nDefault duration: {duration}{duration_default}\nMax options: {max_options}{max_options_default}\nAnonymous by default: {anonymous}\nButton colors: {colors}",
+    ),
+    ("settings_bot_default", " (bot default)"),
+    ("yes", "yes"),
+    ("no", "no"),
+    ("colors_primary", "blurple"),
+    ("colors_secondary", "grey"),
+    ("colors_success", "green"),
+    ("colors_danger", "red"),
+    ("colors_cycle", "a different one for each option"),
+    (
+        "stats",
+        "Polls created: {created}\nVotes cast: {votes}\nActive polls: {active}",
+    ),
+    ("purged_one", "Closed 1 poll."),
+    ("purged", "Closed {count} polls."),
+];
+
+const DE: &[(&str, &str)] = &[
+    // errors
+    ("error_no_options", "Du musst mindestens eine Option angeben."),
+    (
+        "error_invisible_option",
+        "Optionen müssen sichtbaren Text enthalten, nicht nur unsichtbare Zeichen.",
+    ),
+    (
+        "error_too_many_options",
+        "Zu viele Optionen ({count}), diese Umfrage kann höchstens {max} haben.",
+    ),
+    (
+        "error_option_too_long",
+        "Die Option `{option}` ist zu lang, Optionen dürfen höchstens {max} Zeichen lang sein.",
+    ),
+    ("error_duplicate_options_one", "Die Optionen enthalten 1 Duplikat."),
+    (
+        "error_duplicate_options",
+        "Die Optionen enthalten {count} Duplikate.",
+    ),
+    (
+        "error_invalid_duration",
+        "Ungültige Dauer `{duration}`, erwartet wird etwas wie 30m, 2h, 1d oder 1h30m.",
+    ),
+    (
+        "error_duration_too_long",
+        "Die Dauer darf höchstens 7 Tage (7d) betragen.",
+    ),
+    (
+        "error_invalid_end_time",
+        "Ungültige Endzeit `{ends}`, erwartet wird etwas wie 2024-06-01T18:00Z.",
+    ),
+    ("error_end_in_past", "Die Endzeit liegt in der Vergangenheit."),
+    (
+        "error_invalid_start_time",
+        "Ungültige Startzeit `{starts}`, erwartet wird etwas wie 2h oder 2024-06-01T18:00Z.",
+    ),
+    ("error_start_in_past", "Die Startzeit liegt in der Vergangenheit."),
+    (
+        "error_start_too_late",
+        "Umfragen können höchstens 7 Tage im Voraus geplant werden.",
+    ),
+    (
+        "error_invalid_image",
+        "Ungültiges Bild `{image}`, erwartet wird ein Link, der mit http:// oder https:// beginnt.",
+    ),
+    (
+        "error_invalid_weights",
+        "Ungültige Gewichtung `{weights}`, erwartet wird etwas wie @Mod=2, @Admin=3.",
+    ),
+    ("error_weight_too_high", "Gewichtungen dürfen höchstens {max} betragen."),
+    (
+        "error_duration_and_end",
+        "Eine Umfrage kann nicht sowohl eine Dauer als auch eine Endzeit haben.",
+    ),
+    ("error_poll_ended", "Diese Umfrage ist bereits beendet."),
+    ("error_poll_closed", "Diese Umfrage ist geschlossen."),
+    (
+        "error_multi_and_ranked",
+        "Eine Umfrage kann nicht gleichzeitig Mehrfachauswahl und Rangfolge haben.",
+    ),
+    (
+        "error_poll_not_found",
+        "Zu dieser Nachricht wurde keine aktive Umfrage gefunden.",
+    ),
+    (
+        "error_not_creator",
+        "Nur die Person, die die Umfrage erstellt hat, kann das tun.",
+    ),
+    (
+        "error_too_many_polls",
+        "Du kannst höchstens {max} Umfragen gleichzeitig laufen lassen, schließe eine, um eine neue zu starten.",
+    ),
+    (
+        "error_anonymous",
+        "Die Umfrage ist anonym, ihre Teilnehmenden können nicht angezeigt werden.",
+    ),
+    (
+        "error_results_hidden",
+        "Die Ergebnisse der Umfrage sind bis zu ihrem Ende verborgen.",
+    ),
+    (
+        "error_reserved_option",
+        "`{option}` kann in dieser Umfrage nicht als Option verwendet werden.",
+    ),
+    (
+        "error_missing_role",
+        "Du hast keine Berechtigung, in dieser Umfrage abzustimmen.",
+    ),
+    (
+        "error_unverified",
+        "Nur Konten, die älter als {days} Tage sind und die Mitgliedschaftsprüfung des Servers abgeschlossen haben, können an dieser Umfrage teilnehmen.",
+    ),
+    ("error_not_owner", "Nur der Besitzer des Bots kann das tun."),
+    (
+        "error_option_not_found",
+        "Die Umfrage hat keine Option namens `{option}`.",
+    ),
+    (
+        "error_option_exists",
+        "Die Umfrage hat bereits eine Option namens `{option}`.",
+    ),
+    (
+        "error_draft_expired",
+        "Die Vorschau ist abgelaufen, starte die Umfrage erneut.",
+    ),
+    ("error_option_full", "`{option}` ist voll."),
+    (
+        "error_cooldown_one",
+        "Bitte warte 1 Sekunde, bevor du hier eine weitere Umfrage startest.",
+    ),
+    (
+        "error_cooldown",
+        "Bitte warte {secs} Sekunden, bevor du hier eine weitere Umfrage startest.",
+    ),
+    // poll message
+    ("poll", "Umfrage"),
+    ("thread_name", "Ergebnisse: {title}"),
+    ("ends", "Endet"),
+    ("ended", "Beendet"),
+    ("vote", "Abstimmen:"),
+    ("final_results", "Umfrage geschlossen, Endergebnis:"),
+    ("attribution", "Umfrage von <@{creator}> • gestartet am <t:{started}:f>"),
+    (
+        "ranked_hint",
+        "Klicke die Optionen in der Reihenfolge deiner Präferenz an, Erstpräferenzen werden angezeigt:",
+    ),
+    ("quorum_failed", "Die Umfrage hat das Quorum nicht erreicht."),
+    (
+        "quorum_missing_one",
+        "Es fehlt noch 1 Stimme bis zum Quorum.",
+    ),
+    (
+        "quorum_missing",
+        "Es fehlen noch {missing} Stimmen bis zum Quorum.",
+    ),
+    ("quorum_reached", "Quorum erreicht."),
+    ("quorum_leading", "Quorum erreicht. Führend: {winner}"),
+    ("winner", "Gewinner: {winner}"),
+    ("tie", "Gleichstand zwischen {winners}"),
+    (
+        "tie_runoff",
+        "Gleichstand zwischen {winners}, eine Stichwahl ist nötig",
+    ),
+    ("quorum_tied", "Quorum erreicht. Gleichstand: {winners}"),
+    ("abstained", "Enthaltungen: {count}"),
+    ("weighted_votes_one", "{votes} (1 Person)"),
+    ("weighted_votes", "{votes} ({count} Personen)"),
+    ("total_votes_one", "Stimmen insgesamt: {total} (von 1 Person)"),
+    (
+        "total_votes",
+        "Stimmen insgesamt: {total} (von {voters} Personen)",
+    ),
+    (
+        "turnout",
+        "{voters} von {eligible} berechtigten Mitgliedern haben abgestimmt.",
+    ),
+    (
+        "results_hidden",
+        "Ergebnisse bis zum Ende der Umfrage verborgen.",
+    ),
+    ("and_more", "…und {count} weitere"),
+    ("round", "Runde {round}: {tallies}"),
+    ("eliminated", " — ausgeschieden: {options}"),
+    ("results_title", "Ergebnisse für **{title}** ({link}):"),
+    ("results", "Ergebnisse für {link}:"),
+    // components
+    ("button_abstain", "Enthaltung"),
+    ("button_see_voters", "Teilnehmende anzeigen"),
+    ("button_my_vote", "Meine Stimme"),
+    ("my_vote", "Du hast gestimmt für: {votes}"),
+    ("my_vote_ranking", "Deine Rangfolge: {ranking}"),
+    ("my_vote_abstained", "Du hast dich enthalten."),
+    ("my_vote_none", "Du hast noch nicht abgestimmt."),
+    ("button_post", "Umfrage posten"),
+    ("button_cancel", "Abbrechen"),
+    ("button_unschedule", "Geplante Umfrage abbrechen"),
+    ("button_add_option", "Option hinzufügen"),
+    ("add_option_title", "Option hinzufügen"),
+    ("add_option_label", "Option"),
+    ("choose_options", "Optionen wählen"),
+    ("choose_option", "Option wählen"),
+    // replies and announcements
+    ("draft_posted", "Umfrage gepostet."),
+    ("posted_link", "Deine Umfrage wurde gepostet: {link}"),
+    ("draft_cancelled", "Umfrage abgebrochen."),
+    ("scheduled", "Umfrage geplant, sie startet {start}."),
+    ("unscheduled", "Geplante Umfrage abgebrochen."),
+    (
+        "ended_no_votes",
+        "Umfrage beendet — es wurden keine Stimmen abgegeben.",
+    ),
+    ("ended_winner", "Umfrage beendet — Gewinner: {winner}"),
+    ("ended_tie", "Umfrage beendet — Gleichstand zwischen {winners}"),
+    (
+        "ended_tie_runoff",
+        "Umfrage beendet — Gleichstand zwischen {winners}, eine Stichwahl ist nötig",
+    ),
+    ("reminder_one", "Noch 1 Minute zum Abstimmen!"),
+    ("reminder", "Noch {minutes} Minuten zum Abstimmen!"),
+    ("duplicates_removed_one", "1 doppelte Option entfernt."),
+    ("duplicates_removed", "{count} doppelte Optionen entfernt."),
+    ("poll_closed", "Umfrage geschlossen."),
+    ("transferred", "Die Umfrage gehört jetzt <@{user}>."),
+    ("renamed", "`{old}` in `{new}` umbenannt."),
+    ("poll_refreshed", "Umfrage aktualisiert."),
+    ("extended", "Die Umfrage endet jetzt <t:{end}:R>, am <t:{end}:f>."),
+    ("voters_abstained", "Enthaltungen"),
+    ("voters_none", "keine Stimmen"),
+    ("list_empty", "In diesem Kanal gibt es keine aktiven Umfragen."),
+    ("list", "Aktive Umfragen in diesem Kanal ({count}):"),
+    ("listing", "- {name}: {leading}, endet <t:{end}:R>"),
+    ("listing_hidden", "Ergebnisse verborgen"),
+    ("listing_no_votes", "noch keine Stimmen"),
+    ("listing_leader", "{leader} führt"),
+    ("listing_tie", "Gleichstand zwischen {leaders}"),
+    ("reloaded", "{count} Befehle auf diesem Server registriert."),
+    ("reloaded_global", "{count} Befehle global registriert."),
+    ("reload_failed", "Die Befehle konnten nicht registriert werden: {error}"),
+    (
+        "settings",
+        "Umfrage-Einstellungen für diesen Server:\nStandarddauer: {duration}{duration_default}\nMaximale Optionen: {max_options}{max_options_default}\nStandardmäßig anonym: {anonymous}\nButton-Farben: {colors}",
+    ),
+    ("settings_bot_default", " (Standard des Bots)"),
+    ("yes", "ja"),
+    ("no", "nein"),
+    ("colors_primary", "Blurple"),
+    ("colors_secondary", "grau"),
+    ("colors_success", "grün"),
+    ("colors_danger", "rot"),
+    ("colors_cycle", "eine andere für jede Option"),
+    (
+        "stats",
+        "Erstellte Umfragen: {created}\nAbgegebene Stimmen: {votes}\nAktive Umfragen: {active}",
+    ),
+    ("purged_one", "1 Umfrage geschlossen."),
+    ("purged", "{count} Umfragen geschlossen."),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_placeholders() {
+        let text = format(
+            "en-US",
+            "error_too_many_options",
+            &[("count", &30), ("max", &25)],
+        );
+        assert_eq!(
+            text,
+            "Too many options (30), this poll can have at most 25."
+        );
+    }
+
+    #[test]
+    fn arguments_are_not_formatted() {
+        // the option is replaced before the max, which mustn't be replaced within it
+        let text = format(
+            "en-US",
+            "error_option_too_long",
+            &[("option", &"{max} {option}"), ("max", &72)],
+        );
+        assert_eq!(
+            text,
+            "The option `{max} {option}` is too long, options can be at most 72 characters."
+        );
+        let text = format(
+            "en-US",
+            "listing",
+            &[("name", &"{end}"), ("leading", &"{name}"), ("end", &1)],
+        );
+        assert_eq!(text, "- {end}: {name}, ends <t:1:R>");
+    }
+}
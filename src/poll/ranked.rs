@@ -0,0 +1,189 @@
+//! Instant-runoff tabulation for ranked polls.
+
+/// The first-preference votes of the remaining options in a round, and the options eliminated
+/// at the end of it
+pub struct Round<'a> {
+    pub tallies: Vec<(&'a str, u32)>,
+    pub eliminated: Vec<&'a str>,
+}
+
+pub struct Runoff<'a> {
+    pub rounds: Vec<Round<'a>>,
+    // multiple winners if the last remaining options are tied, none if no votes were cast
+    pub winners: Vec<&'a str>,
+}
+
+/// Counts each ballot towards its highest ranked remaining option with the ballot's weight,
+/// eliminating the options with the fewest votes each round until one of them has a majority.
+/// All the options tied for the fewest votes are eliminated together, unless that would
+/// eliminate every remaining option, in which case they're all winners
+pub fn instant_runoff<'a>(options: &[&'a str], ballots: &[(&[String], u32)]) -> Runoff<'a> {
+    let mut remaining = options.to_vec();
+    let mut rounds = vec![];
+    loop {
+        let mut tallies = remaining
+            .iter()
+            .map(|option| (*option, 0))
+            .collect::<Vec<_>>();
+        // ballots that only rank eliminated options no longer count
+        let mut active = 0;
+        for (ballot, weight) in ballots {
+            let choice = ballot
+                .iter()
+                .find_map(|choice| tallies.iter().position(|(option, _)| option == choice));
+            if let Some(i) = choice {
+                tallies[i].1 += weight;
+                active += weight;
+            }
+        }
+        if active == 0 {
+            rounds.push(Round {
+                tallies,
+                eliminated: vec![],
+            });
+            return Runoff {
+                rounds,
+                winners: vec![],
+            };
+        }
+
+        let max = tallies.iter().map(|(_, votes)| *votes).max().unwrap_or(0);
+        if max * 2 > active {
+            let winners = tallies
+                .iter()
+                .filter(|(_, votes)| *votes == max)
+                .map(|(option, _)| *option)
+                .collect();
+            rounds.push(Round {
+                tallies,
+                eliminated: vec![],
+            });
+            return Runoff { rounds, winners };
+        }
+
+        let min = tallies.iter().map(|(_, votes)| *votes).min().unwrap_or(0);
+        let eliminated = tallies
+            .iter()
+            .filter(|(_, votes)| *votes == min)
+            .map(|(option, _)| *option)
+            .collect::<Vec<_>>();
+        if eliminated.len() == remaining.len() {
+            // every remaining option is tied, so none can be eliminated
+            rounds.push(Round {
+                tallies,
+                eliminated: vec![],
+            });
+            return Runoff {
+                rounds,
+                winners: remaining,
+            };
+        }
+        remaining.retain(|option| !eliminated.contains(option));
+        rounds.push(Round {
+            tallies,
+            eliminated,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ballot(choices: &[&str]) -> Vec<String> {
+        choices.iter().map(|choice| choice.to_string()).collect()
+    }
+
+    fn run<'a>(options: &[&'a str], ballots: &[(Vec<String>, u32)]) -> Runoff<'a> {
+        let ballots = ballots
+            .iter()
+            .map(|(ballot, weight)| (ballot.as_slice(), *weight))
+            .collect::<Vec<_>>();
+        instant_runoff(options, &ballots)
+    }
+
+    #[test]
+    fn majority_wins_in_first_round() {
+        let ballots = [
+            (ballot(&["a", "b"]), 1),
+            (ballot(&["a"]), 1),
+            (ballot(&["b", "a"]), 1),
+        ];
+        let runoff = run(&["a", "b", "c"], &ballots);
+        assert_eq!(runoff.winners, ["a"]);
+        assert_eq!(runoff.rounds.len(), 1);
+        assert_eq!(runoff.rounds[0].tallies, [("a", 2), ("b", 1), ("c", 0)]);
+        assert!(runoff.rounds[0].eliminated.is_empty());
+    }
+
+    #[test]
+    fn eliminated_options_transfer_votes() {
+        let ballots = [
+            (ballot(&["a"]), 1),
+            (ballot(&["a"]), 1),
+            (ballot(&["b"]), 1),
+            (ballot(&["b"]), 1),
+            (ballot(&["c", "b"]), 1),
+        ];
+        let runoff = run(&["a", "b", "c"], &ballots);
+        assert_eq!(runoff.winners, ["b"]);
+        assert_eq!(runoff.rounds.len(), 2);
+        assert_eq!(runoff.rounds[0].eliminated, ["c"]);
+        assert_eq!(runoff.rounds[1].tallies, [("a", 2), ("b", 3)]);
+    }
+
+    #[test]
+    fn options_tied_for_last_are_eliminated_together() {
+        let ballots = [
+            (ballot(&["a"]), 1),
+            (ballot(&["a"]), 1),
+            (ballot(&["b"]), 1),
+            (ballot(&["b"]), 1),
+            (ballot(&["c", "a"]), 1),
+            (ballot(&["d", "b"]), 1),
+        ];
+        let runoff = run(&["a", "b", "c", "d"], &ballots);
+        assert_eq!(runoff.rounds[0].eliminated, ["c", "d"]);
+        // the remaining options are tied, so neither can be eliminated
+        assert_eq!(runoff.rounds[1].tallies, [("a", 3), ("b", 3)]);
+        assert!(runoff.rounds[1].eliminated.is_empty());
+        assert_eq!(runoff.winners, ["a", "b"]);
+    }
+
+    #[test]
+    fn exhausted_ballots_stop_counting() {
+        let ballots = [
+            (ballot(&["a"]), 1),
+            (ballot(&["a"]), 1),
+            (ballot(&["a"]), 1),
+            (ballot(&["b"]), 1),
+            (ballot(&["b"]), 1),
+            (ballot(&["c"]), 1),
+        ];
+        let runoff = run(&["a", "b", "c"], &ballots);
+        // 3 of 6 isn't a majority, but 3 of the 5 ballots left after c is eliminated is
+        assert_eq!(runoff.rounds[0].eliminated, ["c"]);
+        assert_eq!(runoff.rounds[1].tallies, [("a", 3), ("b", 2)]);
+        assert_eq!(runoff.winners, ["a"]);
+    }
+
+    #[test]
+    fn no_ballots_have_no_winner() {
+        let runoff = run(&["a", "b"], &[]);
+        assert!(runoff.winners.is_empty());
+        assert_eq!(runoff.rounds.len(), 1);
+        assert_eq!(runoff.rounds[0].tallies, [("a", 0), ("b", 0)]);
+    }
+
+    #[test]
+    fn ballots_count_with_their_weight() {
+        let ballots = [
+            (ballot(&["a"]), 1),
+            (ballot(&["a"]), 1),
+            (ballot(&["b"]), 3),
+        ];
+        let runoff = run(&["a", "b"], &ballots);
+        assert_eq!(runoff.rounds[0].tallies, [("a", 2), ("b", 3)]);
+        assert_eq!(runoff.winners, ["b"]);
+    }
+}
@@ -0,0 +1,215 @@
+//! Best-effort recovery of polls from their messages, for polls that weren't saved.
+
+use super::{
+    i18n, option_id, ButtonColors, PollData, PollKind, SortOrder, TiePolicy, ABSTAIN_ID,
+    ADD_OPTION_ID, CLONE_COMMAND, COMMAND, MY_VOTE_ID, OPTION_PREFIX, POLLS, SELECT_MENU_ID,
+    VOTERS_ID,
+};
+use anyhow::Context as _;
+use serenity::{
+    http::Http,
+    model::{
+        application::component::{ActionRowComponent, ButtonStyle},
+        channel::Message,
+        id::{ChannelId, InteractionId, UserId},
+        Timestamp,
+    },
+};
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+// how many of the channel's latest messages are searched for polls
+const MESSAGES_SEARCHED: u64 = 100;
+
+/// Searches the channel's recent messages for open polls posted by the bot that aren't stored,
+/// and restores them so that their buttons keep working
+pub async fn recover(http: &Http, channel_id: ChannelId, bot_id: UserId) -> anyhow::Result<()> {
+    let messages = channel_id
+        .messages(http, |retriever| retriever.limit(MESSAGES_SEARCHED))
+        .await
+        .with_context(|| format!("failed to get the messages in {channel_id}"))?;
+    let mut recovered = 0;
+    for message in messages {
+        if message.author.id != bot_id {
+            continue;
+        }
+        let Some((id, poll_data)) = recover_poll(&message) else {
+            continue;
+        };
+        if poll_data.closed {
+            continue;
+        }
+        let mut lock = POLLS.shard(id).write().await;
+        if lock.contains_key(&id) {
+            continue;
+        }
+        lock.insert(id, poll_data);
+        recovered += 1;
+    }
+    tracing::info!("recovered {recovered} polls from {channel_id}");
+    Ok(())
+}
+
+/// Reconstructs the poll from its message, with the votes on the buttons counted without knowing
+/// who cast them
+pub(super) fn recover_poll(message: &Message) -> Option<(InteractionId, PollData)> {
+    let interaction = message.interaction.as_ref()?;
+    if interaction.name != COMMAND && interaction.name != CLONE_COMMAND {
+        return None;
+    }
+    let embed = message.embeds.first()?;
+    // the embed's timestamp is the poll's end time
+    let end_time = Timestamp::parse(embed.timestamp.as_deref()?).ok()?;
+    let end_time = SystemTime::UNIX_EPOCH + Duration::from_secs(end_time.unix_timestamp() as u64);
+
+    let mut options = vec![];
+    let mut caps = HashMap::new();
+    let mut recovered_tallies = HashMap::new();
+    let mut kind = PollKind::Single;
+    let mut anonymous = true;
+    let mut abstain = false;
+    let mut open_additions = false;
+    let mut styles = vec![];
+    // polls past their end time have been closed even if their components can't tell
+    let mut closed = end_time <= SystemTime::now();
+    for component in message.components.iter().flat_map(|row| &row.components) {
+        match component {
+            ActionRowComponent::Button(button) => {
+                // closed polls have their buttons disabled
+                closed |= button.disabled;
+                match button.custom_id.as_deref()? {
+                    VOTERS_ID => anonymous = false,
+                    MY_VOTE_ID => {}
+                    ADD_OPTION_ID => open_additions = true,
+                    custom_id => {
+                        let id = custom_id.strip_prefix(OPTION_PREFIX).unwrap_or(custom_id);
+                        let (text, votes) = parse_label(button.label.as_deref()?, &mut caps)?;
+                        if id == ABSTAIN_ID {
+                            abstain = true;
+                            recovered_tallies.insert(ABSTAIN_ID.to_string(), votes);
+                        } else {
+                            styles.push(button.style);
+                            options.push((
+                                id.parse::<usize>().ok(),
+                                match &button.emoji {
+                                    Some(emoji) => format!("{emoji} {text}"),
+                                    None => text.to_string(),
+                                },
+                            ));
+                            recovered_tallies.insert(text.to_string(), votes);
+                        }
+                    }
+                }
+            }
+            ActionRowComponent::SelectMenu(menu) => {
+                if menu.custom_id.as_deref() != Some(SELECT_MENU_ID) {
+                    continue;
+                }
+                if menu.max_values.unwrap_or(1) > 1 {
+                    kind = PollKind::Multi;
+                }
+                for option in &menu.options {
+                    let (text, votes) = parse_label(&option.label, &mut caps)?;
+                    if option.value == ABSTAIN_ID {
+                        abstain = true;
+                        recovered_tallies.insert(ABSTAIN_ID.to_string(), votes);
+                    } else {
+                        options.push((
+                            option.value.parse::<usize>().ok(),
+                            match &option.emoji {
+                                Some(emoji) => format!("{emoji} {text}"),
+                                None => text.to_string(),
+                            },
+                        ));
+                        recovered_tallies.insert(text.to_string(), votes);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if options.is_empty() {
+        return None;
+    }
+    // the components' IDs are the options' positions, which the message's components keep
+    // referring to until it's updated, while the options may be shown in a different order
+    options.sort_by_key(|(position, _)| *position);
+    let options = options.into_iter().map(|(_, option)| option).collect();
+    // buttons in different colors mean that the poll cycled through them
+    let colors = match styles.as_slice() {
+        [] => ButtonColors::Primary,
+        [style, rest @ ..] if rest.iter().all(|s| s == style) => match style {
+            ButtonStyle::Secondary => ButtonColors::Secondary,
+            ButtonStyle::Success => ButtonColors::Success,
+            ButtonStyle::Danger => ButtonColors::Danger,
+            _ => ButtonColors::Primary,
+        },
+        _ => ButtonColors::Cycle,
+    };
+
+    let mut poll_data = PollData {
+        // the start time isn't shown on open polls, so the message's is the closest
+        start_time: SystemTime::UNIX_EPOCH
+            + Duration::from_secs(message.timestamp.unix_timestamp() as u64),
+        end_time,
+        scheduled: false,
+        reminded: false,
+        pinned: message.pinned,
+        creator: interaction.user.id,
+        guild_id: message.guild_id,
+        channel_id: message.channel_id,
+        message_id: message.id,
+        closed,
+        // polls without a title are shown with a generic one
+        title: embed
+            .title
+            .clone()
+            .filter(|title| title != i18n::text(i18n::DEFAULT_LOCALE, "poll")),
+        image: embed.image.as_ref().map(|image| image.url.clone()),
+        options,
+        kind,
+        anonymous,
+        // polls with hidden results don't show the votes, so they can't be recovered
+        hide_results: false,
+        thread_results: false,
+        quorum: None,
+        target_votes: None,
+        abstain,
+        role: None,
+        eligible: None,
+        ties: TiePolicy::Report,
+        // the options are read in the order they were shown in
+        sort: SortOrder::Entered,
+        colors,
+        open_additions,
+        case_sensitive: false,
+        verified_only: false,
+        ping: None,
+        locale: i18n::DEFAULT_LOCALE.to_string(),
+        votes: HashMap::new(),
+        caps,
+        weights: HashMap::new(),
+        voter_weights: HashMap::new(),
+        recovered_tallies,
+        tallies: HashMap::new(),
+        weighted_tallies: HashMap::new(),
+    };
+    poll_data.recount();
+    Some((interaction.id, poll_data))
+}
+
+/// Parses the option's text and votes from a label like `Pizza: 3`, or `Carpool: 2/4` for
+/// options with a capacity
+fn parse_label<'a>(label: &'a str, caps: &mut HashMap<String, u32>) -> Option<(&'a str, u32)> {
+    let (text, votes) = label.rsplit_once(": ")?;
+    let votes = match votes.split_once('/') {
+        Some((votes, cap)) => {
+            caps.insert(option_id(text).to_string(), cap.parse().ok()?);
+            votes.parse().ok()?
+        }
+        None => votes.parse().ok()?,
+    };
+    Some((text, votes))
+}
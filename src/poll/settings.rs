@@ -0,0 +1,85 @@
+//! Per-guild defaults for new polls, set by each guild's admins and saved to disk so that they
+//! survive restarts.
+
+use super::{ButtonColors, Config};
+use anyhow::Context as _;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    model::id::GuildId,
+    prelude::{Mutex, RwLock},
+};
+use std::{collections::HashMap, path::Path, time::Duration};
+
+static SETTINGS: Lazy<RwLock<HashMap<GuildId, GuildSettings>>> = Lazy::new(Default::default);
+// held while the settings are saved, so that concurrent saves don't share the temporary file or
+// overwrite newer settings with older ones
+static SAVING: Lazy<Mutex<()>> = Lazy::new(Default::default);
+
+/// A guild's overrides of the operator's defaults
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct GuildSettings {
+    // the duration of polls that don't specify one
+    pub default_duration: Option<Duration>,
+    // how many options each poll can have, at most the operator's limit
+    pub max_options: Option<usize>,
+    // whether polls are anonymous unless they specify otherwise
+    pub anonymous: bool,
+    // the colors of the options' buttons in new polls
+    pub colors: ButtonColors,
+}
+
+impl GuildSettings {
+    /// The operator's config with the guild's overrides applied
+    pub fn apply(&self, config: &Config) -> Config {
+        Config {
+            default_duration: self.default_duration.unwrap_or(config.default_duration),
+            max_options: self
+                .max_options
+                .map_or(config.max_options, |max| max.min(config.max_options)),
+            ..*config
+        }
+    }
+}
+
+/// The guild's settings, or the defaults outside of guilds and for guilds that haven't set any
+pub async fn get(guild_id: Option<GuildId>) -> GuildSettings {
+    let Some(guild_id) = guild_id else {
+        return GuildSettings::default();
+    };
+    SETTINGS
+        .read()
+        .await
+        .get(&guild_id)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Changes the guild's settings and saves every guild's settings to the file
+pub async fn set(guild_id: GuildId, settings: GuildSettings, path: &Path) -> anyhow::Result<()> {
+    let _saving = SAVING.lock().await;
+    let json = {
+        let mut lock = SETTINGS.write().await;
+        lock.insert(guild_id, settings);
+        serde_json::to_vec(&*lock).context("failed to serialize settings")?
+    };
+    super::write_file(path, json).await
+}
+
+/// Restores the settings saved with `set`, doing nothing if the file doesn't exist
+pub async fn load(path: &Path) -> anyhow::Result<()> {
+    let json = match tokio::fs::read(path).await {
+        Ok(json) => json,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let settings: HashMap<GuildId, GuildSettings> =
+        serde_json::from_slice(&json).context("failed to deserialize settings")?;
+    tracing::info!(
+        "restored the settings of {} guilds from {}",
+        settings.len(),
+        path.display()
+    );
+    *SETTINGS.write().await = settings;
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! Storage for the active polls, split into shards so that polls in different shards
+//! can be voted on concurrently.
+
+use super::PollData;
+use serenity::{
+    model::id::{InteractionId, MessageId},
+    prelude::RwLock,
+};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+const SHARDS: usize = 16;
+
+pub type Shard = HashMap<InteractionId, PollData>;
+
+pub struct Polls {
+    shards: Vec<RwLock<Shard>>,
+}
+
+impl Polls {
+    pub fn new() -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| RwLock::new(HashMap::new())).collect(),
+        }
+    }
+
+    /// Returns the shard that contains the poll with the given ID
+    pub fn shard(&self, id: InteractionId) -> &RwLock<Shard> {
+        let mut hasher = DefaultHasher::new();
+        id.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % SHARDS]
+    }
+
+    pub fn shards(&self) -> &[RwLock<Shard>] {
+        &self.shards
+    }
+
+    /// Removes the polls that started first until at most the maximum number of polls is left
+    pub async fn evict_oldest(&self, max_polls: usize) -> Vec<(InteractionId, PollData)> {
+        let mut polls = vec![];
+        for shard in &self.shards {
+            polls.extend(
+                shard
+                    .read()
+                    .await
+                    .iter()
+                    .map(|(id, poll_data)| (poll_data.start_time, *id)),
+            );
+        }
+        if polls.len() <= max_polls {
+            return vec![];
+        }
+        polls.sort_unstable();
+        let excess = polls.len() - max_polls;
+        let mut evicted = vec![];
+        for (_, id) in polls.into_iter().take(excess) {
+            // the poll may have been removed in the meantime
+            if let Some(poll_data) = self.shard(id).write().await.remove(&id) {
+                evicted.push((id, poll_data));
+            }
+        }
+        evicted
+    }
+
+    /// Finds the ID of the poll that was posted as the given message
+    pub async fn find_by_message(&self, message_id: MessageId) -> Option<InteractionId> {
+        for shard in &self.shards {
+            let lock = shard.read().await;
+            let id = lock
+                .iter()
+                // scheduled polls don't have a message yet
+                .find(|(_, poll_data)| !poll_data.scheduled && poll_data.message_id == message_id)
+                .map(|(id, _)| *id);
+            if id.is_some() {
+                return id;
+            }
+        }
+        None
+    }
+}